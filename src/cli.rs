@@ -0,0 +1,420 @@
+use std::fmt;
+
+use raytracing_rust::filter::{Filter, FilterKind};
+use raytracing_rust::render::tile::TileOrder;
+use raytracing_rust::samplers::SamplerKind;
+use raytracing_rust::scene::{CameraSettings, ImageSettings};
+use raytracing_rust::tonemap::{ToneMapper, Transfer};
+use raytracing_rust::vec3::Vec3;
+
+pub const USAGE: &str = "\
+Usage: raytracing_rust [OPTIONS]
+
+Renders the built-in random scene, or the scene given with --scene.
+Options override the values from the scene file.
+
+Options:
+  -o, --output <FILE>         Output image: .png, .ppm (ASCII), .pnm (binary),
+                              or .exr, .pfm, .hdr for linear radiance
+                              [default: test.ppm]
+      --scene <FILE>          JSON scene description to render
+      --width <PIXELS>        Image width [default: 1200]
+      --aspect-ratio <RATIO>  Width over height, as 1.5 or 3:2 [default: 3:2]
+      --samples <N>           Samples per pixel, or the most any pixel takes
+                              with a noise threshold [default: 500]
+      --min-samples <N>       Samples every pixel takes before it may stop,
+                              and between noise checks [default: 16]
+      --noise-threshold <E>   Stop sampling a pixel once the relative standard
+                              error of its luminance is below E, 0 to always
+                              take every sample [default: 0]
+      --heatmap <FILE>        Also write an image of the samples taken per
+                              pixel
+      --filter <FILTER>       Pixel reconstruction filter: box, tent, gaussian,
+                              mitchell or lanczos [default: box]
+      --filter-radius <PIXELS>
+                              Filter radius [default: 0.5 for box, 1 for tent,
+                              1.5 for gaussian, 2 for mitchell, 3 for lanczos]
+      --max-depth <N>         Maximum number of bounces per path [default: 50]
+      --threads <N>           Number of render threads [default: number of CPUs]
+      --tile-size <PIXELS>    Width and height of the tiles rendered by each
+                              thread [default: 32]
+      --tile-order <ORDER>    scanline, spiral or hilbert [default: spiral]
+      --sampler <KIND>        independent, stratified, halton or sobol
+                              [default: sobol]
+      --seed <N>              Seed for the random scene and for sampling;
+                              the same seed gives the same image [default: 0]
+      --exposure <STOPS>      Exposure adjustment for 8-bit output [default: 0]
+      --tone-map <OPERATOR>   clamp, reinhard, reinhard_extended, aces or hable
+                              [default: clamp]
+      --white-point <L>       Luminance mapped to white by reinhard_extended
+                              [default: 4]
+      --transfer <FUNCTION>   srgb or gamma [default: gamma]
+      --gamma <GAMMA>         Exponent of the gamma transfer function [default: 2]
+      --lookfrom <X,Y,Z>      Camera position [default: 13,2,3]
+      --lookat <X,Y,Z>        Point the camera looks at [default: 0,0,0]
+      --vup <X,Y,Z>           Camera up direction [default: 0,1,0]
+      --vfov <DEGREES>        Vertical field of view [default: 20]
+      --aperture <SIZE>       Lens aperture, 0 for a pinhole [default: 0.1]
+      --focus-dist <DIST>     Distance to the plane in focus [default: 10]
+      --shutter <OPEN,CLOSE>  Times the shutter opens and closes, blurring
+                              moving objects in between [default: 0,1]
+      --fog <DENSITY>         Density of a white fog filling the scene, 0 for
+                              clear air [default: 0]
+  -h, --help                  Print this help
+";
+
+/// Options that take a value.
+const FLAGS: &[&str] = &[
+    "-o",
+    "--output",
+    "--scene",
+    "--width",
+    "--aspect-ratio",
+    "--samples",
+    "--min-samples",
+    "--noise-threshold",
+    "--heatmap",
+    "--filter",
+    "--filter-radius",
+    "--max-depth",
+    "--threads",
+    "--tile-size",
+    "--tile-order",
+    "--sampler",
+    "--seed",
+    "--exposure",
+    "--tone-map",
+    "--white-point",
+    "--transfer",
+    "--gamma",
+    "--lookfrom",
+    "--lookat",
+    "--vup",
+    "--vfov",
+    "--aperture",
+    "--focus-dist",
+    "--shutter",
+    "--fog",
+];
+
+#[derive(Debug, PartialEq)]
+pub struct CliError(String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Command line options. Unset values fall back to the scene file, then to
+/// the built-in defaults.
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    pub help: bool,
+    pub output: Option<String>,
+    pub scene: Option<String>,
+    pub width: Option<u32>,
+    pub aspect_ratio: Option<f64>,
+    pub samples_per_pixel: Option<u32>,
+    pub min_samples_per_pixel: Option<u32>,
+    pub noise_threshold: Option<f64>,
+    pub heatmap: Option<String>,
+    pub filter: Option<FilterKind>,
+    pub filter_radius: Option<f64>,
+    pub max_depth: Option<u32>,
+    pub threads: Option<u32>,
+    pub tile_size: Option<u32>,
+    pub tile_order: Option<TileOrder>,
+    pub sampler: Option<SamplerKind>,
+    pub seed: Option<u64>,
+    pub exposure: Option<f64>,
+    pub tone_mapper: Option<ToneMapper>,
+    pub white_point: Option<f64>,
+    pub transfer: Option<Transfer>,
+    pub gamma: Option<f64>,
+    pub lookfrom: Option<Vec3>,
+    pub lookat: Option<Vec3>,
+    pub vup: Option<Vec3>,
+    pub vfov: Option<f64>,
+    pub aperture: Option<f64>,
+    pub focus_dist: Option<f64>,
+    pub shutter: Option<(f64, f64)>,
+    pub fog: Option<f64>,
+}
+
+impl Options {
+    /// Parses the arguments following the program name.
+    pub fn parse<I>(args: I) -> Result<Options, CliError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "-h" || arg == "--help" {
+                options.help = true;
+                continue;
+            }
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value)),
+                _ => (arg.clone(), None),
+            };
+            if !flag.starts_with('-') {
+                return Err(CliError(format!("unexpected argument '{}'", arg)));
+            }
+            if !FLAGS.contains(&flag.as_str()) {
+                return Err(CliError(format!("unknown option '{}'", flag)));
+            }
+            let value = match inline_value {
+                Some(value) => value.to_string(),
+                None => match args.next() {
+                    Some(value) => value,
+                    None => return Err(CliError(format!("{} requires a value", flag))),
+                },
+            };
+
+            match flag.as_str() {
+                "-o" | "--output" => options.output = Some(value),
+                "--scene" => options.scene = Some(value),
+                "--width" => options.width = Some(positive_integer(&flag, &value)?),
+                "--aspect-ratio" => options.aspect_ratio = Some(ratio(&flag, &value)?),
+                "--samples" => options.samples_per_pixel = Some(positive_integer(&flag, &value)?),
+                "--min-samples" => {
+                    options.min_samples_per_pixel = Some(positive_integer(&flag, &value)?)
+                }
+                "--noise-threshold" => options.noise_threshold = Some(non_negative(&flag, &value)?),
+                "--heatmap" => options.heatmap = Some(value),
+                "--filter" => {
+                    options.filter = Some(choice(
+                        &flag,
+                        &value,
+                        FilterKind::from_name,
+                        FilterKind::NAMES,
+                    )?)
+                }
+                "--filter-radius" => options.filter_radius = Some(positive(&flag, &value)?),
+                "--max-depth" => options.max_depth = Some(positive_integer(&flag, &value)?),
+                "--threads" => options.threads = Some(positive_integer(&flag, &value)?),
+                "--sampler" => {
+                    options.sampler = Some(choice(
+                        &flag,
+                        &value,
+                        SamplerKind::from_name,
+                        SamplerKind::NAMES,
+                    )?)
+                }
+                "--seed" => {
+                    options.seed = Some(
+                        value
+                            .parse()
+                            .map_err(|_| invalid(&flag, &value, "a non-negative integer"))?,
+                    )
+                }
+                "--tile-size" => options.tile_size = Some(positive_integer(&flag, &value)?),
+                "--tile-order" => {
+                    options.tile_order = Some(choice(
+                        &flag,
+                        &value,
+                        TileOrder::from_name,
+                        TileOrder::NAMES,
+                    )?)
+                }
+                "--exposure" => options.exposure = Some(number(&flag, &value)?),
+                "--tone-map" => {
+                    options.tone_mapper = Some(choice(
+                        &flag,
+                        &value,
+                        ToneMapper::from_name,
+                        ToneMapper::NAMES,
+                    )?)
+                }
+                "--white-point" => options.white_point = Some(positive(&flag, &value)?),
+                "--transfer" => {
+                    options.transfer =
+                        Some(choice(&flag, &value, Transfer::from_name, Transfer::NAMES)?)
+                }
+                "--gamma" => options.gamma = Some(positive(&flag, &value)?),
+                "--lookfrom" => options.lookfrom = Some(vector(&flag, &value)?),
+                "--lookat" => options.lookat = Some(vector(&flag, &value)?),
+                "--vup" => options.vup = Some(vector(&flag, &value)?),
+                "--vfov" => options.vfov = Some(positive(&flag, &value)?),
+                "--aperture" => options.aperture = Some(non_negative(&flag, &value)?),
+                "--focus-dist" => options.focus_dist = Some(positive(&flag, &value)?),
+                "--shutter" => options.shutter = Some(interval(&flag, &value)?),
+                "--fog" => options.fog = Some(non_negative(&flag, &value)?),
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(options)
+    }
+
+    pub fn apply(&self, image: &mut ImageSettings, camera: &mut CameraSettings) {
+        image.width = self.width.unwrap_or(image.width);
+        image.aspect_ratio = self.aspect_ratio.unwrap_or(image.aspect_ratio);
+        image.samples_per_pixel = self.samples_per_pixel.unwrap_or(image.samples_per_pixel);
+        image.min_samples_per_pixel = self
+            .min_samples_per_pixel
+            .unwrap_or(image.min_samples_per_pixel);
+        image.noise_threshold = self.noise_threshold.unwrap_or(image.noise_threshold);
+        image.max_depth = self.max_depth.unwrap_or(image.max_depth);
+        if let Some(kind) = self.filter {
+            image.filter = Filter::new(kind);
+        }
+        image.filter.radius = self.filter_radius.unwrap_or(image.filter.radius);
+        let display = &mut image.display;
+        display.exposure = self.exposure.unwrap_or(display.exposure);
+        display.tone_mapper = self.tone_mapper.unwrap_or(display.tone_mapper);
+        display.white_point = self.white_point.unwrap_or(display.white_point);
+        display.transfer = self.transfer.unwrap_or(display.transfer);
+        display.gamma = self.gamma.unwrap_or(display.gamma);
+        camera.lookfrom = self.lookfrom.unwrap_or(camera.lookfrom);
+        camera.lookat = self.lookat.unwrap_or(camera.lookat);
+        camera.vup = self.vup.unwrap_or(camera.vup);
+        camera.vfov = self.vfov.unwrap_or(camera.vfov);
+        camera.aperture = self.aperture.unwrap_or(camera.aperture);
+        camera.focus_dist = self.focus_dist.unwrap_or(camera.focus_dist);
+        if let Some((open, close)) = self.shutter {
+            camera.shutter_open = open;
+            camera.shutter_close = close;
+        }
+    }
+}
+
+fn invalid(flag: &str, value: &str, expected: &str) -> CliError {
+    CliError(format!(
+        "invalid value '{}' for {}: expected {}",
+        value, flag, expected
+    ))
+}
+
+fn positive_integer(flag: &str, value: &str) -> Result<u32, CliError> {
+    match value.parse::<u32>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(invalid(flag, value, "a positive integer")),
+    }
+}
+
+fn number(flag: &str, value: &str) -> Result<f64, CliError> {
+    match value.parse::<f64>() {
+        Ok(n) if n.is_finite() => Ok(n),
+        _ => Err(invalid(flag, value, "a number")),
+    }
+}
+
+fn positive(flag: &str, value: &str) -> Result<f64, CliError> {
+    match number(flag, value)? {
+        n if n > 0.0 => Ok(n),
+        _ => Err(invalid(flag, value, "a number greater than zero")),
+    }
+}
+
+fn non_negative(flag: &str, value: &str) -> Result<f64, CliError> {
+    match number(flag, value)? {
+        n if n >= 0.0 => Ok(n),
+        _ => Err(invalid(flag, value, "a number not below zero")),
+    }
+}
+
+fn ratio(flag: &str, value: &str) -> Result<f64, CliError> {
+    let expected = "a ratio like 1.5 or 3:2";
+    let ratio = match value.split_once([':', '/']) {
+        Some((w, h)) => match (w.parse::<f64>(), h.parse::<f64>()) {
+            (Ok(w), Ok(h)) if h > 0.0 => w / h,
+            _ => return Err(invalid(flag, value, expected)),
+        },
+        None => value
+            .parse::<f64>()
+            .map_err(|_| invalid(flag, value, expected))?,
+    };
+    if ratio > 0.0 && ratio.is_finite() {
+        Ok(ratio)
+    } else {
+        Err(invalid(flag, value, expected))
+    }
+}
+
+fn choice<T>(
+    flag: &str,
+    value: &str,
+    from_name: fn(&str) -> Option<T>,
+    names: &[&str],
+) -> Result<T, CliError> {
+    from_name(value).ok_or_else(|| invalid(flag, value, &format!("one of {}", names.join(", "))))
+}
+
+fn vector(flag: &str, value: &str) -> Result<Vec3, CliError> {
+    let parts: Vec<Result<f64, _>> = value.split(',').map(|p| p.trim().parse::<f64>()).collect();
+    match parts.as_slice() {
+        [Ok(x), Ok(y), Ok(z)] => Ok(Vec3::new(*x, *y, *z)),
+        _ => Err(invalid(
+            flag,
+            value,
+            "three comma separated numbers like 13,2,3",
+        )),
+    }
+}
+
+fn interval(flag: &str, value: &str) -> Result<(f64, f64), CliError> {
+    let parts: Vec<Result<f64, _>> = value.split(',').map(|p| p.trim().parse::<f64>()).collect();
+    match parts.as_slice() {
+        [Ok(open), Ok(close)] if open <= close => Ok((*open, *close)),
+        _ => Err(invalid(
+            flag,
+            value,
+            "two comma separated times, the first not after the second, like 0,1",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, CliError> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse_options_test() {
+        let options = parse(&[
+            "-o",
+            "out.ppm",
+            "--width=400",
+            "--aspect-ratio",
+            "16:9",
+            "--lookfrom",
+            "1,2,3",
+            "--transfer=srgb",
+        ])
+        .unwrap();
+
+        assert_eq!(options.output, Some("out.ppm".to_string()));
+        assert_eq!(options.width, Some(400));
+        assert_eq!(options.aspect_ratio, Some(16.0 / 9.0));
+        assert_eq!(options.lookfrom, Some(Vec3::new(1.0, 2.0, 3.0)));
+        assert_eq!(options.transfer, Some(Transfer::Srgb));
+        assert_eq!(options.samples_per_pixel, None);
+    }
+
+    #[test]
+    fn parse_errors_test() {
+        let error = |args: &[&str]| parse(args).err().unwrap().to_string();
+
+        assert_eq!(
+            error(&["--samples", "-3"]),
+            "invalid value '-3' for --samples: expected a positive integer"
+        );
+        assert_eq!(error(&["--fov", "30"]), "unknown option '--fov'");
+        assert_eq!(error(&["--fov"]), "unknown option '--fov'");
+        assert_eq!(error(&["--width"]), "--width requires a value");
+        assert_eq!(error(&["out.ppm"]), "unexpected argument 'out.ppm'");
+        assert_eq!(
+            error(&["--tone-map", "filmic"]),
+            "invalid value 'filmic' for --tone-map: expected one of clamp, reinhard, reinhard_extended, aces, hable"
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use crate::core::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Aabb {
+    minimum: Point3,
+    maximum: Point3,
+}
+
+impl Aabb {
+    pub fn new(a: Point3, b: Point3) -> Aabb {
+        Aabb {
+            minimum: Vec3::minimum(a, b),
+            maximum: Vec3::maximum(a, b),
+        }
+    }
+
+    pub fn min(&self) -> Point3 {
+        self.minimum
+    }
+
+    pub fn max(&self) -> Point3 {
+        self.maximum
+    }
+
+    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.hit_interval(ray, t_min, t_max).is_some()
+    }
+
+    /// Range of `t` within [`t_min`, `t_max`] over which `ray` is inside the
+    /// box, or `None` when it misses.
+    pub fn hit_interval(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let mut t_min = t_min;
+        let mut t_max = t_max;
+        for a in 0..3 {
+            let inv_d = 1.0 / ray.direction()[a];
+            let mut t0 = (self.minimum[a] - ray.origin()[a]) * inv_d;
+            let mut t1 = (self.maximum[a] - ray.origin()[a]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max <= t_min {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+
+    pub fn surrounding_box(box0: &Aabb, box1: &Aabb) -> Aabb {
+        Aabb {
+            minimum: Vec3::minimum(box0.minimum, box1.minimum),
+            maximum: Vec3::maximum(box0.maximum, box1.maximum),
+        }
+    }
+
+    /// Grows the box on every axis where it is thinner than `delta`, so that
+    /// flat primitives still enclose a volume the slab test can hit.
+    pub fn pad(&self, delta: f64) -> Aabb {
+        let extent = self.maximum - self.minimum;
+        let half = Vec3::new(
+            if extent.x() < delta { delta / 2.0 } else { 0.0 },
+            if extent.y() < delta { delta / 2.0 } else { 0.0 },
+            if extent.z() < delta { delta / 2.0 } else { 0.0 },
+        );
+        Aabb {
+            minimum: self.minimum - half,
+            maximum: self.maximum + half,
+        }
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        0.5 * (self.minimum + self.maximum)
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let d = self.maximum - self.minimum;
+        2.0 * (d.x() * d.y() + d.y() * d.z() + d.z() * d.x())
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let d = self.maximum - self.minimum;
+        if d.x() > d.y() && d.x() > d.z() {
+            0
+        } else if d.y() > d.z() {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hit_test() {
+        let bbox = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+        let toward = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        let away = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, -1.0));
+        let beside = Ray::new(Point3::new(2.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+
+        assert!(bbox.hit(&toward, 0.001, f64::INFINITY));
+        assert!(!bbox.hit(&away, 0.001, f64::INFINITY));
+        assert!(!bbox.hit(&beside, 0.001, f64::INFINITY));
+        assert!(!bbox.hit(&toward, 0.001, 3.0));
+        assert_eq!(
+            bbox.hit_interval(&toward, 0.001, f64::INFINITY),
+            Some((4.0, 6.0))
+        );
+    }
+
+    #[test]
+    fn surrounding_box_test() {
+        let box0 = Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0));
+        let box1 = Aabb::new(Point3::new(-1.0, 0.5, 0.5), Point3::new(0.5, 2.0, 0.5));
+        let bbox = Aabb::surrounding_box(&box0, &box1);
+
+        assert_eq!(bbox.min(), Point3::new(-1.0, 0.0, 0.0));
+        assert_eq!(bbox.max(), Point3::new(1.0, 2.0, 1.0));
+        assert_eq!(bbox.longest_axis(), 1);
+        assert_eq!(bbox.surface_area(), 16.0);
+    }
+}
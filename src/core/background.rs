@@ -0,0 +1,80 @@
+use crate::core::ray::Ray;
+use crate::vec3::Color;
+
+/// Radiance returned for rays that leave the scene without hitting anything.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Background {
+    Black,
+    Solid(Color),
+    /// Blend between `bottom` for rays pointing straight down and `top` for
+    /// rays pointing straight up.
+    Gradient {
+        bottom: Color,
+        top: Color,
+    },
+}
+
+impl Background {
+    pub fn value(&self, ray: &Ray) -> Color {
+        match self {
+            Background::Black => Color::new(0.0, 0.0, 0.0),
+            Background::Solid(color) => *color,
+            Background::Gradient { bottom, top } => {
+                let unit_direction = ray.direction().unit_vector();
+                let t = 0.5 * (unit_direction.y() + 1.0);
+                (1.0 - t) * *bottom + t * *top
+            }
+        }
+    }
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::{Point3, Vec3};
+
+    fn ray(direction: Vec3) -> Ray {
+        Ray::new(Point3::new(0.0, 0.0, 0.0), direction)
+    }
+
+    #[test]
+    fn gradient_endpoints_test() {
+        let background = Background::Gradient {
+            bottom: Color::new(1.0, 0.0, 0.0),
+            top: Color::new(0.0, 0.0, 1.0),
+        };
+
+        let up = background.value(&ray(Vec3::new(0.0, 3.0, 0.0)));
+        let down = background.value(&ray(Vec3::new(0.0, -3.0, 0.0)));
+        let level = background.value(&ray(Vec3::new(2.0, 0.0, 0.0)));
+        assert_eq!(up, Color::new(0.0, 0.0, 1.0));
+        assert_eq!(down, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(level, Color::new(0.5, 0.0, 0.5));
+    }
+
+    #[test]
+    fn solid_and_black_test() {
+        let directions = [
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            Vec3::new(1.0, 0.2, -3.0),
+        ];
+        for direction in directions.iter() {
+            let solid = Background::Solid(Color::new(0.2, 0.4, 0.6));
+            assert_eq!(solid.value(&ray(*direction)), Color::new(0.2, 0.4, 0.6));
+            assert_eq!(
+                Background::Black.value(&ray(*direction)),
+                Color::new(0.0, 0.0, 0.0)
+            );
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use crate::core::degrees_to_radians;
 use crate::core::ray::Ray;
+use crate::samplers::{concentric_disk, Sampler};
 use crate::vec3::{Point3, Vec3};
 
 pub struct Camera {
@@ -10,6 +11,8 @@ pub struct Camera {
     u: Vec3,
     v: Vec3,
     lens_radius: f64,
+    shutter_open: f64,
+    shutter_close: f64,
 }
 
 impl Camera {
@@ -43,15 +46,31 @@ impl Camera {
             u,
             v,
             lens_radius: aperture / 2.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
         }
     }
 
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let rd = self.lens_radius * Vec3::random_in_unit_disk();
+    /// Keeps the shutter open from `open` to `close`, giving rays times
+    /// spread evenly over that interval.
+    pub fn with_shutter(mut self, open: f64, close: f64) -> Camera {
+        self.shutter_open = open;
+        self.shutter_close = close;
+        self
+    }
+
+    pub fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray {
+        let rd = self.lens_radius * concentric_disk(sampler.get_2d());
         let offset = self.u * rd.x() + self.v * rd.y();
-        Ray::new(
+        let time = if self.shutter_close > self.shutter_open {
+            self.shutter_open + sampler.get_1d() * (self.shutter_close - self.shutter_open)
+        } else {
+            self.shutter_open
+        };
+        Ray::with_time(
             self.origin + offset,
             self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
+            time,
         )
     }
 }
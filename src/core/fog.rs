@@ -0,0 +1,69 @@
+use crate::core::ray::Ray;
+use crate::core::HitRecord;
+use crate::objects::henyey_greenstein::phase_function;
+use crate::objects::MaterialType;
+use crate::textures::TextureType;
+use crate::vec3::{Color, Vec3};
+
+/// Homogeneous fog filling the space between surfaces. Rays that leave the
+/// scene are not fogged, so the background stays visible and lights the fog,
+/// while distant surfaces fade into it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fog {
+    /// Chance of scattering per unit distance; zero for clear air.
+    pub density: f64,
+    /// Fraction of the light scattered rather than absorbed.
+    pub albedo: Color,
+    /// Henyey-Greenstein asymmetry of the scattering, from backwards at -1
+    /// through isotropic at 0 to forwards at 1.
+    pub anisotropy: f64,
+}
+
+impl Fog {
+    pub fn is_clear(&self) -> bool {
+        self.density <= 0.0
+    }
+
+    /// Fraction of light crossing `distance` without being scattered.
+    pub fn transmittance(&self, distance: f64) -> f64 {
+        if self.is_clear() {
+            1.0
+        } else {
+            (-self.density * distance).exp()
+        }
+    }
+
+    /// Material scattering light off the fog.
+    pub fn phase_function(&self) -> MaterialType {
+        phase_function(TextureType::from(self.albedo), self.anisotropy)
+    }
+
+    /// Record of `ray` scattering off the fog at `t`, where `phase_function`
+    /// is the fog's own.
+    pub fn scattering<'a>(
+        &self,
+        ray: &Ray,
+        t: f64,
+        phase_function: &'a MaterialType,
+    ) -> HitRecord<'a> {
+        HitRecord {
+            p: ray.at(t),
+            // Arbitrary, fog has no surface.
+            normal: Vec3::new(1.0, 0.0, 0.0),
+            material: phase_function,
+            t,
+            front_face: true,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Fog {
+            density: 0.0,
+            albedo: Color::new(1.0, 1.0, 1.0),
+            anisotropy: 0.0,
+        }
+    }
+}
@@ -1,15 +1,151 @@
-use rand::Rng;
 use std::sync::Arc;
 
+use crate::core::aabb::Aabb;
+use crate::core::background::Background;
+use crate::core::fog::Fog;
 use crate::core::ray::Ray;
+use crate::objects::hittable_list::HitTableList;
 use crate::objects::MaterialType;
+use crate::samplers::Sampler;
 use crate::vec3::{Color, Point3, Vec3};
 
+pub mod aabb;
+pub mod background;
 pub mod camera;
+pub mod fog;
+pub mod onb;
 pub mod ray;
+pub mod rng;
 
-pub trait HitTable {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool;
+pub trait HitTable: Send + Sync {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool;
+    /// Writes the box enclosing the object into `output_box`, or returns false
+    /// when the object is unbounded.
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool;
+
+    /// Like `hit`, but passing through participating media, which only dim
+    /// the light along a ray. Shadow rays use it to find what they reach.
+    fn hit_surface<'a>(
+        &'a self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord<'a>,
+    ) -> bool {
+        self.hit(ray, t_min, t_max, rec)
+    }
+
+    /// Estimates the fraction of light travelling along `ray` from `t_min`
+    /// to `t_max` that passes through the participating media in the object.
+    fn transmittance(&self, _ray: &Ray, _t_min: f64, _t_max: f64) -> f64 {
+        1.0
+    }
+
+    /// Appends to `hits`, nearest first, every crossing of the object's
+    /// surface by `ray` between `t_min` and `t_max`, each recorded as `hit`
+    /// would, so `front_face` tells whether the ray is going in or out.
+    /// Constructive solid geometry uses it to find the stretches of a ray
+    /// inside closed objects. By default the crossings are found one after
+    /// another with `hit`.
+    fn hit_all<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, hits: &mut Vec<HitRecord<'a>>) {
+        let mut t = t_min;
+        let mut rec = HitRecord::default();
+        while self.hit(ray, t, t_max, &mut rec) {
+            // Steps just past the crossing, by an amount that keeps up with
+            // the precision of its distance.
+            t = rec.t + 1e-9 * rec.t.abs().max(1.0);
+            hits.push(rec);
+        }
+    }
+
+    /// Density, per unit solid angle, with which `random` picks `direction`
+    /// from `origin`. Objects that cannot be sampled as lights return zero.
+    fn pdf_value(&self, _origin: Point3, _direction: Vec3) -> f64 {
+        0.0
+    }
+
+    /// Returns a direction from `origin` towards a random point on the object.
+    fn random(&self, _origin: Point3, _sampler: &mut dyn Sampler) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
+}
+
+impl<T> HitTable for Box<T>
+where
+    T: HitTable + ?Sized,
+{
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        (**self).hit(ray, t_min, t_max, rec)
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        (**self).bounding_box(output_box)
+    }
+
+    fn hit_surface<'a>(
+        &'a self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord<'a>,
+    ) -> bool {
+        (**self).hit_surface(ray, t_min, t_max, rec)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        (**self).transmittance(ray, t_min, t_max)
+    }
+
+    fn hit_all<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, hits: &mut Vec<HitRecord<'a>>) {
+        (**self).hit_all(ray, t_min, t_max, hits)
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        (**self).pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        (**self).random(origin, sampler)
+    }
+}
+
+impl<T> HitTable for Arc<T>
+where
+    T: HitTable + ?Sized,
+{
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        (**self).hit(ray, t_min, t_max, rec)
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        (**self).bounding_box(output_box)
+    }
+
+    fn hit_surface<'a>(
+        &'a self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord<'a>,
+    ) -> bool {
+        (**self).hit_surface(ray, t_min, t_max, rec)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        (**self).transmittance(ray, t_min, t_max)
+    }
+
+    fn hit_all<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, hits: &mut Vec<HitRecord<'a>>) {
+        (**self).hit_all(ray, t_min, t_max, hits)
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        (**self).pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        (**self).random(origin, sampler)
+    }
 }
 
 pub trait Material {
@@ -19,19 +155,58 @@ pub trait Material {
         hit_record: &HitRecord,
         attenuation: &mut Color,
         scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
     ) -> bool;
+
+    fn emitted(&self, _rec: &HitRecord) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    /// Density, per unit solid angle, with which `scatter` picks the direction
+    /// of `scattered`. Specular materials, whose directions cannot be chosen
+    /// by light sampling, return zero.
+    fn scattering_pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        0.0
+    }
+}
+
+pub trait Texture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color;
 }
 
-#[derive(Clone, Copy, Default)]
-pub struct HitRecord {
+/// Material of records that have not hit anything.
+static NO_MATERIAL: MaterialType = MaterialType::None;
+
+/// Details of a ray hitting an object, borrowing the material from the
+/// object so candidate hits can be copied freely.
+#[derive(Clone, Copy)]
+pub struct HitRecord<'a> {
     pub p: Point3,
     pub normal: Vec3,
-    pub material: MaterialType,
+    pub material: &'a MaterialType,
     pub t: f64,
+    pub u: f64,
+    pub v: f64,
+    pub barycentric: (f64, f64),
     pub front_face: bool,
 }
 
-impl HitRecord {
+impl Default for HitRecord<'_> {
+    fn default() -> Self {
+        HitRecord {
+            p: Point3::default(),
+            normal: Vec3::default(),
+            material: &NO_MATERIAL,
+            t: 0.0,
+            u: 0.0,
+            v: 0.0,
+            barycentric: (0.0, 0.0),
+            front_face: false,
+        }
+    }
+}
+
+impl HitRecord<'_> {
     pub fn set_face_normal(&mut self, ray: &Ray, outward_normal: Vec3) {
         self.front_face = Vec3::dot(ray.direction(), outward_normal) < 0.0;
         self.normal = if self.front_face {
@@ -42,44 +217,115 @@ impl HitRecord {
     }
 }
 
-pub fn ray_color<T>(ray: Ray, world: &Arc<T>, depth: u32) -> Color
+/// Estimates the radiance arriving along `ray`.
+///
+/// At every diffuse bounce one of `lights` is sampled directly, and the result
+/// is combined with the material's own sampling by multiple importance
+/// sampling with the power heuristic. With no lights this is plain path
+/// tracing. Unless `fog` is clear, a ray may scatter off it before reaching
+/// the surface it hits.
+pub fn ray_color<T, L>(
+    ray: Ray,
+    world: &T,
+    lights: &HitTableList<L>,
+    background: &Background,
+    fog: &Fog,
+    depth: u32,
+    sampler: &mut dyn Sampler,
+) -> Color
 where
     T: HitTable,
+    L: HitTable,
 {
-    if depth <= 0 {
-        return Color::new(0.0, 0.0, 0.0);
-    }
-    let mut rec: HitRecord = Default::default();
-    if world.hit(&ray, 0.001, f64::INFINITY, &mut rec) {
+    let mut color = Color::new(0.0, 0.0, 0.0);
+    let mut throughput = Color::new(1.0, 1.0, 1.0);
+    let mut ray = ray;
+    // Pdf of the scattering that produced `ray`, zero for camera rays and
+    // specular bounces.
+    let mut scatter_pdf = 0.0;
+    let fog_phase_function = fog.phase_function();
+
+    for _ in 0..depth {
+        let mut rec: HitRecord = Default::default();
+        if !world.hit(&ray, 0.001, f64::INFINITY, &mut rec) {
+            color += throughput * background.value(&ray);
+            break;
+        }
+        if !fog.is_clear() {
+            // Free flight distance, with the probability of passing a
+            // distance unscattered equal to the fog's transmittance.
+            let flight = -(1.0 - sampler.get_1d()).ln() / fog.density;
+            let t = flight / ray.direction().length();
+            if t < rec.t {
+                rec = fog.scattering(&ray, t, &fog_phase_function);
+            }
+        }
+
+        let emitted = rec.material.emitted(&rec);
+        if scatter_pdf > 0.0 && !lights.is_empty() {
+            let light_pdf = lights.pdf_value(ray.origin(), ray.direction());
+            color += throughput * emitted * power_heuristic(scatter_pdf, light_pdf);
+        } else {
+            color += throughput * emitted;
+        }
+
         let mut scattered: Ray = Default::default();
         let mut attenuation: Color = Default::default();
+        if !rec
+            .material
+            .scatter(&ray, &rec, &mut attenuation, &mut scattered, sampler)
+        {
+            break;
+        }
 
-        if let MaterialType::Lambertian(l) = rec.material {
-            if l.scatter(&ray, &rec, &mut attenuation, &mut scattered) {
-                return attenuation * ray_color(scattered, world, depth - 1);
-            }
-        } else if let MaterialType::Metal(m) = rec.material {
-            if m.scatter(&ray, &rec, &mut attenuation, &mut scattered) {
-                return attenuation * ray_color(scattered, world, depth - 1);
-            }
-        } else if let MaterialType::Dielectric(d) = rec.material {
-            if d.scatter(&ray, &rec, &mut attenuation, &mut scattered) {
-                return attenuation * ray_color(scattered, world, depth - 1);
-            }
+        scatter_pdf = rec.material.scattering_pdf(&ray, &rec, &scattered);
+        if scatter_pdf > 0.0 && !lights.is_empty() {
+            color +=
+                throughput * attenuation * sample_light(&ray, &rec, world, lights, fog, sampler);
         }
-        return Color::new(0.0, 0.0, 0.0);
+        throughput = throughput * attenuation;
+        ray = scattered;
     }
-    let unit_direction = ray.direction().unit_vector();
-    let t = 0.5 * (unit_direction.y() + 1.0);
-    (1.0 - t) * Color::new(1.0, 1.0, 1.0) + t * Color::new(0.5, 0.7, 1.0)
+
+    color
 }
 
-pub fn random_f64() -> f64 {
-    rand::thread_rng().gen_range(0.0..1.0)
+/// Light arriving at `rec` from a direction chosen by light sampling, divided
+/// by the material's attenuation.
+fn sample_light<T, L>(
+    r_in: &Ray,
+    rec: &HitRecord,
+    world: &T,
+    lights: &HitTableList<L>,
+    fog: &Fog,
+    sampler: &mut dyn Sampler,
+) -> Color
+where
+    T: HitTable,
+    L: HitTable,
+{
+    let black = Color::new(0.0, 0.0, 0.0);
+    let light_ray = Ray::with_time(rec.p, lights.random(rec.p, sampler), r_in.time());
+    let light_pdf = lights.pdf_value(rec.p, light_ray.direction());
+    let scatter_pdf = rec.material.scattering_pdf(r_in, rec, &light_ray);
+    if light_pdf <= 0.0 || scatter_pdf <= 0.0 {
+        return black;
+    }
+
+    let mut light_rec: HitRecord = Default::default();
+    if !world.hit_surface(&light_ray, 0.001, f64::INFINITY, &mut light_rec) {
+        return black;
+    }
+    light_rec.material.emitted(&light_rec)
+        * world.transmittance(&light_ray, 0.001, light_rec.t)
+        * fog.transmittance(light_rec.t * light_ray.direction().length())
+        * (scatter_pdf / light_pdf)
+        * power_heuristic(light_pdf, scatter_pdf)
 }
 
-pub fn random_range_f64(min: f64, max: f64) -> f64 {
-    min + (max - min) * random_f64()
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let p2 = pdf * pdf;
+    p2 / (p2 + other_pdf * other_pdf)
 }
 
 pub fn clamp(x: f64, min: f64, max: f64) -> f64 {
@@ -95,3 +341,19 @@ pub fn clamp(x: f64, min: f64, max: f64) -> f64 {
 pub fn degrees_to_radians(degrees: f64) -> f64 {
     degrees * std::f64::consts::PI / 180.0
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn power_heuristic_test() {
+        let pdfs = [(1.0, 1.0), (0.2, 3.0), (5.0, 0.01), (0.7, 0.0)];
+        for &(a, b) in pdfs.iter() {
+            let sum = power_heuristic(a, b) + power_heuristic(b, a);
+            assert!((sum - 1.0).abs() < 1e-12);
+        }
+        assert_eq!(power_heuristic(0.7, 0.0), 1.0);
+        assert_eq!(power_heuristic(1.0, 1.0), 0.5);
+    }
+}
@@ -0,0 +1,65 @@
+use crate::vec3::Vec3;
+
+/// Orthonormal basis whose `w` axis is a given direction.
+pub struct Onb {
+    axis: [Vec3; 3],
+}
+
+impl Onb {
+    pub fn build_from_w(w: Vec3) -> Onb {
+        let unit_w = w.unit_vector();
+        let a = if unit_w.x().abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = Vec3::cross(unit_w, a).unit_vector();
+        let u = Vec3::cross(unit_w, v);
+        Onb {
+            axis: [u, v, unit_w],
+        }
+    }
+
+    pub fn u(&self) -> Vec3 {
+        self.axis[0]
+    }
+
+    pub fn v(&self) -> Vec3 {
+        self.axis[1]
+    }
+
+    pub fn w(&self) -> Vec3 {
+        self.axis[2]
+    }
+
+    /// Transforms a vector from basis coordinates to world coordinates.
+    pub fn local(&self, a: Vec3) -> Vec3 {
+        a.x() * self.axis[0] + a.y() * self.axis[1] + a.z() * self.axis[2]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orthonormal_test() {
+        let directions = [
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(3.0, 0.1, -0.2),
+            Vec3::new(-1.0, 0.0, 0.0),
+            Vec3::new(0.3, -2.0, 0.7),
+        ];
+        for direction in directions.iter() {
+            let onb = Onb::build_from_w(*direction);
+            for axis in [onb.u(), onb.v(), onb.w()].iter() {
+                assert!((axis.length() - 1.0).abs() < 1e-12);
+            }
+            assert!(Vec3::dot(onb.u(), onb.v()).abs() < 1e-12);
+            assert!(Vec3::dot(onb.v(), onb.w()).abs() < 1e-12);
+            assert!(Vec3::dot(onb.w(), onb.u()).abs() < 1e-12);
+            assert!((onb.w() - direction.unit_vector()).length() < 1e-12);
+            assert!((onb.local(Vec3::new(0.0, 0.0, 2.0)) - 2.0 * onb.w()).length() < 1e-12);
+        }
+    }
+}
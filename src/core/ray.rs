@@ -4,11 +4,21 @@ use crate::vec3::{Point3, Vec3};
 pub struct Ray {
     orig: Point3,
     dir: Vec3,
+    tm: f64,
 }
 
 impl Ray {
     pub fn new(orig: Point3, dir: Vec3) -> Ray {
-        Ray { orig, dir }
+        Ray::with_time(orig, dir, 0.0)
+    }
+    /// A ray travelling at instant `time`, which moving objects use to find
+    /// where they are.
+    pub fn with_time(orig: Point3, dir: Vec3, time: f64) -> Ray {
+        Ray {
+            orig,
+            dir,
+            tm: time,
+        }
     }
     pub fn origin(&self) -> Point3 {
         self.orig
@@ -16,6 +26,9 @@ impl Ray {
     pub fn direction(&self) -> Vec3 {
         self.dir
     }
+    pub fn time(&self) -> f64 {
+        self.tm
+    }
     pub fn at(&self, t: f64) -> Point3 {
         self.orig + t * self.dir
     }
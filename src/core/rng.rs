@@ -0,0 +1,75 @@
+/// Small, fast PCG32 generator. Samplers reseed one for every pixel sample
+/// from a hash of the pixel, the sample index and the render seed (see
+/// `samplers::hash`), so images do not depend on how pixels are shared out
+/// between threads.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+    increment: u64,
+}
+
+const MULTIPLIER: u64 = 6364136223846793005;
+const DEFAULT_STREAM: u64 = 1442695040888963407;
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        let mut rng = Rng {
+            state: 0,
+            increment: DEFAULT_STREAM | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(self.increment);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rotation = (old >> 59) as u32;
+        xorshifted.rotate_right(rotation)
+    }
+
+    /// Uniform in [0, 1).
+    pub fn f64(&mut self) -> f64 {
+        let bits = ((self.next_u32() as u64) << 21) ^ ((self.next_u32() as u64) >> 11);
+        bits as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in [min, max).
+    pub fn range_f64(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.f64()
+    }
+
+    /// Uniform integer in [0, n).
+    pub fn below(&mut self, n: usize) -> usize {
+        ((self.f64() * n as f64) as usize).min(n - 1)
+    }
+}
+
+/// Splitmix64 finaliser, scattering the bits of `x` across the result.
+pub fn mix_bits(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rng_test() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let first: Vec<u32> = (0..4).map(|_| a.next_u32()).collect();
+        assert_eq!(first, (0..4).map(|_| b.next_u32()).collect::<Vec<_>>());
+        assert_ne!(Rng::new(43).next_u32(), first[0]);
+
+        let mut rng = Rng::new(7);
+        let mean = (0..10000).map(|_| rng.f64()).sum::<f64>() / 10000.0;
+        assert!((mean - 0.5).abs() < 0.02);
+    }
+}
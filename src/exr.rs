@@ -0,0 +1,134 @@
+use std::io::prelude::*;
+
+use crate::vec3::Color;
+
+const MAGIC: u32 = 20000630;
+const VERSION: u32 = 2;
+const PIXEL_TYPE_FLOAT: i32 = 2;
+
+/// Writes linear radiance, in rows from top to bottom, as an uncompressed
+/// scanline OpenEXR image with 32-bit float R, G and B channels.
+pub fn write<W: Write>(
+    writer: &mut W,
+    width: u32,
+    height: u32,
+    pixels: &[Color],
+) -> std::io::Result<()> {
+    assert_eq!(
+        pixels.len(),
+        (width * height) as usize,
+        "pixel data size mismatch"
+    );
+
+    let mut header = vec![];
+    header.extend_from_slice(&MAGIC.to_le_bytes());
+    header.extend_from_slice(&VERSION.to_le_bytes());
+
+    // Channels are stored in alphabetical order.
+    let mut channels = vec![];
+    for name in ["B", "G", "R"] {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        channels.extend_from_slice(&PIXEL_TYPE_FLOAT.to_le_bytes());
+        // pLinear and three reserved bytes, then x and y sampling.
+        channels.extend_from_slice(&[0, 0, 0, 0]);
+        channels.extend_from_slice(&1i32.to_le_bytes());
+        channels.extend_from_slice(&1i32.to_le_bytes());
+    }
+    channels.push(0);
+    attribute(&mut header, "channels", "chlist", &channels);
+
+    attribute(&mut header, "compression", "compression", &[0]);
+    let mut window = vec![];
+    for v in [0, 0, width as i32 - 1, height as i32 - 1] {
+        window.extend_from_slice(&v.to_le_bytes());
+    }
+    attribute(&mut header, "dataWindow", "box2i", &window);
+    attribute(&mut header, "displayWindow", "box2i", &window);
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    header.push(0);
+    writer.write_all(&header)?;
+
+    // Offset table, one entry per scanline block.
+    let row_size = 3 * 4 * width as u64;
+    let block_size = 8 + row_size;
+    let first = (header.len() + 8 * height as usize) as u64;
+    for y in 0..height as u64 {
+        writer.write_all(&(first + y * block_size).to_le_bytes())?;
+    }
+
+    for (y, row) in pixels.chunks(width as usize).enumerate() {
+        writer.write_all(&(y as i32).to_le_bytes())?;
+        writer.write_all(&(row_size as i32).to_le_bytes())?;
+        for channel in [2, 1, 0] {
+            for color in row {
+                writer.write_all(&(color[channel] as f32).to_le_bytes())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floats(bytes: &[u8]) -> Vec<f32> {
+        bytes
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect()
+    }
+
+    #[test]
+    fn write_header_test() {
+        let pixels = [Color::new(1.0, 2.0, 3.0), Color::new(4.0, 5.0, 6.0)];
+        let mut bytes = vec![];
+        write(&mut bytes, 2, 1, &pixels).unwrap();
+
+        assert_eq!(&bytes[..4], b"\x76\x2f\x31\x01");
+        assert_eq!(&bytes[4..8], &[2, 0, 0, 0]);
+        assert_eq!(&bytes[8..24], b"channels\0chlist\0");
+        assert_eq!(&bytes[24..28], &55i32.to_le_bytes());
+        let channels = &bytes[28..28 + 55];
+        assert_eq!(&channels[..2], b"B\0");
+        assert_eq!(&channels[18..20], b"G\0");
+        assert_eq!(&channels[36..38], b"R\0");
+        assert_eq!(channels[54], 0);
+
+        // The single scanline follows the header and its offset table, with
+        // each channel stored as a run of floats.
+        let block = &bytes[bytes.len() - 32..];
+        let header_len = bytes.len() - 32 - 8;
+        assert_eq!(
+            &bytes[header_len..header_len + 8],
+            &(header_len as u64 + 8).to_le_bytes()
+        );
+        assert_eq!(&block[..4], &0i32.to_le_bytes());
+        assert_eq!(&block[4..8], &24i32.to_le_bytes());
+        assert_eq!(floats(&block[8..]), vec![3.0, 6.0, 2.0, 5.0, 1.0, 4.0]);
+    }
+}
@@ -0,0 +1,137 @@
+/// Shape of the pixel reconstruction filter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FilterKind {
+    /// Equal weight everywhere within the radius.
+    Box,
+    /// Weight falling linearly to zero at the radius.
+    Tent,
+    /// Gaussian with a standard deviation of a third of the radius, shifted
+    /// down to reach zero at the radius.
+    Gaussian,
+    /// Mitchell-Netravali cubic with B = C = 1/3.
+    Mitchell,
+    /// Sinc windowed by a wider sinc reaching zero at the radius.
+    Lanczos,
+}
+
+impl FilterKind {
+    pub const NAMES: &'static [&'static str] = &["box", "tent", "gaussian", "mitchell", "lanczos"];
+
+    pub fn from_name(name: &str) -> Option<FilterKind> {
+        match name {
+            "box" => Some(FilterKind::Box),
+            "tent" => Some(FilterKind::Tent),
+            "gaussian" => Some(FilterKind::Gaussian),
+            "mitchell" => Some(FilterKind::Mitchell),
+            "lanczos" => Some(FilterKind::Lanczos),
+            _ => None,
+        }
+    }
+
+    /// Radius, in pixels, used when none is given.
+    pub fn default_radius(self) -> f64 {
+        match self {
+            FilterKind::Box => 0.5,
+            FilterKind::Tent => 1.0,
+            FilterKind::Gaussian => 1.5,
+            FilterKind::Mitchell => 2.0,
+            FilterKind::Lanczos => 3.0,
+        }
+    }
+}
+
+/// Weights samples by their distance from a pixel centre. Every sample is
+/// added to all pixels within `radius` of it in both x and y, and a pixel's
+/// value is the weighted sum of its samples divided by the sum of weights.
+/// The default, a box of radius one half, keeps each sample in its own pixel
+/// and so averages them as the renderer always did.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Filter {
+    pub kind: FilterKind,
+    pub radius: f64,
+}
+
+impl Filter {
+    pub fn new(kind: FilterKind) -> Filter {
+        Filter {
+            kind,
+            radius: kind.default_radius(),
+        }
+    }
+
+    /// Weight of a sample `x` pixels across and `y` pixels down from a pixel
+    /// centre. Mitchell and Lanczos weights are negative in places.
+    pub fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.evaluate_1d(x) * self.evaluate_1d(y)
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        let r = self.radius;
+        let x = x.abs();
+        if x >= r {
+            return 0.0;
+        }
+        match self.kind {
+            FilterKind::Box => 1.0,
+            FilterKind::Tent => 1.0 - x / r,
+            FilterKind::Gaussian => {
+                let sigma = r / 3.0;
+                let gaussian = |x: f64| (-x * x / (2.0 * sigma * sigma)).exp();
+                gaussian(x) - gaussian(r)
+            }
+            FilterKind::Mitchell => mitchell(2.0 * x / r),
+            FilterKind::Lanczos => sinc(x) * sinc(x / r),
+        }
+    }
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter::new(FilterKind::Box)
+    }
+}
+
+/// Mitchell-Netravali cubic on [0, 2] with B = C = 1/3.
+fn mitchell(x: f64) -> f64 {
+    const B: f64 = 1.0 / 3.0;
+    const C: f64 = 1.0 / 3.0;
+    let value = if x < 1.0 {
+        (12.0 - 9.0 * B - 6.0 * C) * x * x * x
+            + (-18.0 + 12.0 * B + 6.0 * C) * x * x
+            + (6.0 - 2.0 * B)
+    } else {
+        (-B - 6.0 * C) * x * x * x
+            + (6.0 * B + 30.0 * C) * x * x
+            + (-12.0 * B - 48.0 * C) * x
+            + (8.0 * B + 24.0 * C)
+    };
+    value / 6.0
+}
+
+fn sinc(x: f64) -> f64 {
+    if x < 1e-5 {
+        return 1.0;
+    }
+    let x = std::f64::consts::PI * x;
+    x.sin() / x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_test() {
+        for name in FilterKind::NAMES {
+            let filter = Filter::new(FilterKind::from_name(name).unwrap());
+            assert!(filter.evaluate(0.0, 0.0) > 0.0, "{}", name);
+            assert_eq!(filter.evaluate(filter.radius, 0.0), 0.0, "{}", name);
+            assert_eq!(filter.evaluate(0.1, 0.2), filter.evaluate(-0.1, -0.2));
+        }
+
+        let tent = Filter::new(FilterKind::Tent);
+        assert!((tent.evaluate(0.5, 0.0) - 0.5).abs() < 1e-12);
+        // Mitchell dips below zero in its outer lobe.
+        assert!(Filter::new(FilterKind::Mitchell).evaluate(1.5, 0.0) < 0.0);
+    }
+}
@@ -0,0 +1,84 @@
+//! A path tracer following "Ray Tracing in One Weekend" and its sequels.
+//!
+//! Scenes are built from objects in [`objects`] or loaded from JSON with
+//! [`scene::Scene`], and rendered by a [`render::Renderer`] into a
+//! [`ppm::Image`] of linear radiance, which can be saved as PNG, PPM, EXR,
+//! PFM or Radiance HDR.
+//!
+//! ```
+//! use raytracing_rust::core::background::Background;
+//! use raytracing_rust::objects::hittable_list::HitTableList;
+//! use raytracing_rust::objects::lambertian::Lambertian;
+//! use raytracing_rust::objects::sphere::Sphere;
+//! use raytracing_rust::objects::MaterialType;
+//! use raytracing_rust::render::Renderer;
+//! use raytracing_rust::scene::{CameraSettings, ImageSettings};
+//!
+//! let mut world = HitTableList::new();
+//! world.add(Sphere::new(
+//!     0.0, 0.0, 0.0, 1.0,
+//!     MaterialType::Lambertian(Lambertian::new(0.8, 0.1, 0.1)),
+//! ));
+//!
+//! let settings = ImageSettings {
+//!     width: 16,
+//!     aspect_ratio: 1.0,
+//!     samples_per_pixel: 4,
+//!     ..ImageSettings::default()
+//! };
+//! let camera = CameraSettings {
+//!     aperture: 0.0,
+//!     ..CameraSettings::default()
+//! };
+//! let renderer = Renderer::new(settings, &camera, Background::default()).with_threads(2);
+//! let image = renderer.render(&world, &HitTableList::<Sphere>::new());
+//!
+//! assert_eq!((image.width(), image.height()), (16, 16));
+//! ```
+//!
+//! Scenes described in JSON carry their own image, camera and background
+//! settings, and their emissive shapes are sampled directly as lights:
+//!
+//! ```
+//! use std::path::Path;
+//!
+//! use raytracing_rust::objects::bvh::BvhNode;
+//! use raytracing_rust::render::Renderer;
+//! use raytracing_rust::scene::Scene;
+//!
+//! let scene = Scene::parse(
+//!     r#"{
+//!         "image": { "width": 8, "aspect_ratio": 1, "samples_per_pixel": 2 },
+//!         "background": { "type": "black" },
+//!         "materials": { "lamp": { "type": "diffuse_light", "emit": [4, 4, 4] } },
+//!         "objects": [
+//!             { "type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "lamp" }
+//!         ]
+//!     }"#,
+//!     "scene.json",
+//!     Path::new("."),
+//! )
+//! .unwrap();
+//!
+//! let renderer = Renderer::new(scene.image, &scene.camera, scene.background);
+//! let image = renderer.render(&BvhNode::new(scene.world), &scene.lights);
+//! assert!(image.pixel(4, 4).x() > 0.0);
+//! ```
+
+pub mod core;
+mod exr;
+pub mod filter;
+pub mod json;
+pub mod mat4;
+pub mod obj;
+pub mod objects;
+mod pfm;
+mod png;
+pub mod ppm;
+mod radiance;
+pub mod render;
+pub mod samplers;
+pub mod scene;
+pub mod textures;
+pub mod tonemap;
+pub mod vec3;
@@ -1,215 +1,135 @@
-mod core;
-mod objects;
-mod ppm;
-mod vec3;
+mod cli;
 
 use std::env;
+use std::process;
+use std::sync::Arc;
 use std::time::Instant;
-use std::thread;
-use std::sync::{
-    Arc,
-    atomic::{AtomicI32, Ordering},
-    Mutex
-};
 
-use crate::core::camera::Camera;
-use crate::core::HitTable;
-use crate::objects::dielectric::Dielectric;
-use crate::objects::hittable_list::HitTableList;
-use crate::objects::lambertian::Lambertian;
-use crate::objects::metal::Metal;
-use crate::objects::sphere::Sphere;
-use crate::objects::MaterialType;
-use crate::ppm::Image;
-use crate::vec3::{Color, Point3, Vec3};
+use raytracing_rust::core::background::Background;
+use raytracing_rust::core::fog::Fog;
+use raytracing_rust::core::rng::Rng;
+use raytracing_rust::core::HitTable;
+use raytracing_rust::objects::bvh::BvhNode;
+use raytracing_rust::objects::hittable_list::HitTableList;
+use raytracing_rust::ppm::ImageFormat;
+use raytracing_rust::render::tile::TileOrder;
+use raytracing_rust::render::Renderer;
+use raytracing_rust::samplers::SamplerKind;
+use raytracing_rust::scene::{self, CameraSettings, ImageSettings, Scene};
+use raytracing_rust::tonemap::DisplayTransform;
 
-fn random_scene() -> HitTableList<Sphere> {
-    let mut world = HitTableList::new();
-
-    world.add(Sphere::new(
-        0.0,
-        -1000.0,
-        0.0,
-        1000.0,
-        MaterialType::Lambertian(Lambertian::new(0.5, 0.5, 0.5)),
-    ));
-
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat = core::random_f64();
-            let center = Point3::new(
-                a as f64 + 0.9 * core::random_f64(),
-                0.2,
-                b as f64 + 0.9 * core::random_f64(),
-            );
-
-            if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
-                if choose_mat < 0.8 {
-                    let albedo = Vec3::random_vec3() * Vec3::random_vec3();
-                    world.add(Sphere::new(
-                        center.x(),
-                        center.y(),
-                        center.z(),
-                        0.2,
-                        MaterialType::Lambertian(Lambertian::new(
-                            albedo.x(),
-                            albedo.y(),
-                            albedo.z(),
-                        )),
-                    ));
-                } else if choose_mat < 0.95 {
-                    let albedo = Vec3::random_vec3();
-                    let fuzz = core::random_range_f64(0.0, 0.5);
-                    world.add(Sphere::new(
-                        center.x(),
-                        center.y(),
-                        center.z(),
-                        0.2,
-                        MaterialType::Metal(Metal::new(albedo.x(), albedo.y(), albedo.z(), fuzz)),
-                    ));
-                } else {
-                    world.add(Sphere::new(
-                        center.x(),
-                        center.y(),
-                        center.z(),
-                        0.2,
-                        MaterialType::Dielectric(Dielectric::new(1.5)),
-                    ));
-                }
-            }
-        }
-    }
-    world.add(Sphere::new(
-        0.0,
-        1.0,
-        0.0,
-        1.0,
-        MaterialType::Dielectric(Dielectric::new(1.5)),
-    ));
-    world.add(Sphere::new(
-        -4.0,
-        1.0,
-        0.0,
-        1.0,
-        MaterialType::Lambertian(Lambertian::new(0.4, 0.2, 0.1)),
-    ));
-    world.add(Sphere::new(
-        4.0,
-        1.0,
-        0.0,
-        1.0,
-        MaterialType::Metal(Metal::new(0.7, 0.6, 0.5, 0.0)),
-    ));
-
-    world
-}
+use crate::cli::Options;
 
 fn main() {
     let start = Instant::now();
 
-    let args: Vec<String> = env::args().collect();
-    let basename = String::from("test.ppm");
-    let filename = if args.len() >= 2 {
-        &args[1]
-    } else {
-        &basename
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {}\n\nFor more information, try '--help'.", err);
+            process::exit(2);
+        }
     };
-    // Image
-    let aspect_ratio: f64 = 3.0 / 2.0;
-    let image_width: u32 = 1200;
-    let image_height: u32 = (image_width as f64 / aspect_ratio) as u32;
-    let samples_per_pixel: u32 = 500;
-    let max_depth = 50;
+    if options.help {
+        print!("{}", cli::USAGE);
+        return;
+    }
+    let filename = options.output.as_deref().unwrap_or("test.ppm");
+    let seed = options.seed.unwrap_or(0);
+    for path in [Some(filename), options.heatmap.as_deref()]
+        .into_iter()
+        .flatten()
+    {
+        if let Err(err) = ImageFormat::from_path(path) {
+            eprintln!("error: {}", err);
+            process::exit(2);
+        }
+    }
 
     // World
-    let org_world = Arc::new(random_scene());
-
-    // Camera
-    let lookfrom_x = if args.len() >= 3 {
-        args[2].parse::<f64>().unwrap()
-    } else {
-        13.0
-    };
-    let lookfrom_y = if args.len() >= 4 {
-        args[3].parse::<f64>().unwrap()
-    } else {
-        2.0
-    };
-    let lookfrom_z = if args.len() >= 5 {
-        args[4].parse::<f64>().unwrap()
-    } else {
-        3.0
-    };
-
-    let lookfrom = Point3::new(lookfrom_x, lookfrom_y, lookfrom_z);
-    let lookat = Point3::new(0.0, 0.0, 0.0);
-    let vup = Vec3::new(0.0, 1.0, 0.0);
-    let dist_to_focus = 10.0;
-    let aperture = 0.1;
-    let camera = Arc::new(Camera::new(
-        lookfrom,
-        lookat,
-        vup,
-        20.0,
-        aspect_ratio,
-        aperture,
-        dist_to_focus,
-    ));
-
-    // Render
-
-    let mut org_img = Arc::new(Mutex::new(Image::new(image_height, image_width)));
-    let j_idx = Arc::new(AtomicI32::new((image_height-1) as i32));
-    let mut threads = vec![];
-    for _ in 0..16 {
-        let atomic_j = j_idx.clone();
-        let img = org_img.clone();
-        let world = org_world.clone();
-        let cam = camera.clone();
-        let img_height = image_height;
-        let img_width = image_width;
-        let sp_per_pixel = samples_per_pixel;
-        let max_dep = max_depth;
-        threads.push(thread::spawn(move || {
-            loop {
-                let j = atomic_j.fetch_sub(1, Ordering::SeqCst);
-                if j < 0 {
-                    break;
-                }
-                for i in 0..img_width {
-                    let mut pixel_color = Color::new(0.0, 0.0, 0.0);
-                    for _ in 0..sp_per_pixel {
-                        let u = (i as f64 + core::random_f64()) / (img_width - 1) as f64;
-                        let v = (j as f64 + core::random_f64()) / (img_height - 1) as f64;
-                        let r = cam.get_ray(u, v);
-                        pixel_color += core::ray_color(r, &world, max_dep);
+    let (mut settings, mut camera_settings, background, mut fog, world, lights) =
+        match &options.scene {
+            Some(path) => match Scene::load(path) {
+                Ok(scene) => {
+                    for warning in scene.warnings.iter() {
+                        eprintln!("warning: {}", warning);
                     }
-                    img.lock().unwrap().set_color(img_height - (j as u32) - 1, i, &pixel_color, sp_per_pixel);
+                    (
+                        scene.image,
+                        scene.camera,
+                        scene.background,
+                        scene.fog,
+                        BvhNode::new(scene.world),
+                        scene.lights,
+                    )
                 }
-                let left = start.elapsed().as_secs();
-                let hour = left / (60 * 60);
-                let min = left / 60 % 60;
-                let sec = left % 60;
-                println!(
-                    "{}/{} done. {}:{}:{}.",
-                    img_height - j as u32,
-                    img_height,
-                    hour,
-                    min,
-                    sec
-                );
-            }
-        })
-        );
+                Err(err) => {
+                    eprintln!("error: failed to load scene: {}", err);
+                    process::exit(1);
+                }
+            },
+            None => (
+                ImageSettings::default(),
+                CameraSettings::default(),
+                Background::default(),
+                Fog::default(),
+                BvhNode::new(scene::random_scene(&mut Rng::new(seed))),
+                HitTableList::<Arc<dyn HitTable>>::new(),
+            ),
+        };
+    options.apply(&mut settings, &mut camera_settings);
+    fog.density = options.fog.unwrap_or(fog.density);
+    if let Err((key, message)) = settings.validate() {
+        eprintln!("error: image {} {}", key, message);
+        process::exit(2);
+    }
+    if let Err((key, message)) = camera_settings.validate() {
+        eprintln!("error: camera {} {}", key, message);
+        process::exit(2);
     }
-    for thread in threads {
-        thread.join().unwrap();
+
+    // Render
+    let mut renderer = Renderer::new(settings, &camera_settings, background)
+        .with_tiles(
+            options.tile_size.unwrap_or(32),
+            options.tile_order.unwrap_or(TileOrder::Spiral),
+        )
+        .with_sampler(options.sampler.unwrap_or(SamplerKind::Sobol))
+        .with_seed(seed)
+        .with_fog(fog);
+    if let Some(threads) = options.threads {
+        renderer = renderer.with_threads(threads);
+    }
+    let (image, sample_counts) =
+        renderer.render_with_sample_counts(&world, &lights, |_, done, total| {
+            let left = start.elapsed().as_secs();
+            let hour = left / (60 * 60);
+            let min = left / 60 % 60;
+            let sec = left % 60;
+            println!("{}/{} tiles done. {}:{}:{}.", done, total, hour, min, sec);
+        });
+    if let Err(err) = image.save(filename, &settings.display) {
+        eprintln!("error: failed to write {}: {}", filename, err);
+        process::exit(1);
+    }
+    if let Some(path) = &options.heatmap {
+        // The heatmap holds display colours already.
+        let display = DisplayTransform {
+            gamma: 1.0,
+            ..DisplayTransform::default()
+        };
+        if let Err(err) = renderer.heatmap(&sample_counts).save(path, &display) {
+            eprintln!("error: failed to write {}: {}", path, err);
+            process::exit(1);
+        }
+    }
+    if settings.noise_threshold > 0.0 {
+        let total: u64 = sample_counts.iter().map(|&n| n as u64).sum();
+        println!(
+            "{:.1} samples per pixel on average",
+            total as f64 / sample_counts.len() as f64
+        );
     }
-    org_img.lock().unwrap().save(filename).unwrap();
     let end = start.elapsed();
-    println!(
-        "finished in {}.{:03}",
-        end.as_secs(),
-        end.subsec_nanos() / 1_000_000
-    );
+    println!("finished in {}.{:03}", end.as_secs(), end.subsec_millis());
 }
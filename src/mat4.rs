@@ -0,0 +1,238 @@
+use crate::core::degrees_to_radians;
+use crate::vec3::{Point3, Vec3};
+use std::ops::{Index, Mul};
+
+/// 4x4 matrix of an affine transform, acting on column vectors: points pick
+/// up the translation in the last column and directions do not.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat4 {
+    m: [[f64; 4]; 4],
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Mat4::identity()
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Self;
+
+    /// The transform applying `rhs` first and then `self`.
+    fn mul(self, rhs: Self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+        Mat4 { m }
+    }
+}
+
+impl Index<(usize, usize)> for Mat4 {
+    type Output = f64;
+    fn index(&self, (row, column): (usize, usize)) -> &f64 {
+        &self.m[row][column]
+    }
+}
+
+impl Mat4 {
+    /// The matrix with the given rows.
+    pub fn new(rows: [[f64; 4]; 4]) -> Mat4 {
+        Mat4 { m: rows }
+    }
+
+    pub fn identity() -> Mat4 {
+        Mat4::scaling(Vec3::new(1.0, 1.0, 1.0))
+    }
+
+    pub fn translation(offset: Vec3) -> Mat4 {
+        Mat4::new([
+            [1.0, 0.0, 0.0, offset.x()],
+            [0.0, 1.0, 0.0, offset.y()],
+            [0.0, 0.0, 1.0, offset.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scaling(factors: Vec3) -> Mat4 {
+        Mat4::new([
+            [factors.x(), 0.0, 0.0, 0.0],
+            [0.0, factors.y(), 0.0, 0.0],
+            [0.0, 0.0, factors.z(), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Rotation by `degrees` about `axis` through the origin, anticlockwise
+    /// when looking down the axis towards the origin.
+    pub fn rotation(axis: Vec3, degrees: f64) -> Mat4 {
+        let a = axis.unit_vector();
+        let (x, y, z) = (a.x(), a.y(), a.z());
+        let (sin, cos) = degrees_to_radians(degrees).sin_cos();
+        let t = 1.0 - cos;
+        Mat4::new([
+            [
+                t * x * x + cos,
+                t * x * y - sin * z,
+                t * x * z + sin * y,
+                0.0,
+            ],
+            [
+                t * x * y + sin * z,
+                t * y * y + cos,
+                t * y * z - sin * x,
+                0.0,
+            ],
+            [
+                t * x * z - sin * y,
+                t * y * z + sin * x,
+                t * z * z + cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Rotation by `degrees` around the x, then y, then z axis.
+    pub fn rotation_xyz(degrees: Vec3) -> Mat4 {
+        Mat4::rotation(Vec3::new(0.0, 0.0, 1.0), degrees.z())
+            * Mat4::rotation(Vec3::new(0.0, 1.0, 0.0), degrees.y())
+            * Mat4::rotation(Vec3::new(1.0, 0.0, 0.0), degrees.x())
+    }
+
+    /// Places an object at `eye` with its z axis pointing at `target` and its
+    /// y axis as close to `vup` as that allows.
+    pub fn look_at(eye: Point3, target: Point3, vup: Vec3) -> Mat4 {
+        let z = (target - eye).unit_vector();
+        let x = Vec3::cross(vup, z).unit_vector();
+        let y = Vec3::cross(z, x);
+        Mat4::new([
+            [x.x(), y.x(), z.x(), eye.x()],
+            [x.y(), y.y(), z.y(), eye.y()],
+            [x.z(), y.z(), z.z(), eye.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        Mat4 { m }
+    }
+
+    /// The inverse by Gauss-Jordan elimination, or `None` when the matrix is
+    /// singular.
+    pub fn inverse(&self) -> Option<Mat4> {
+        let mut a = self.m;
+        let mut inv = Mat4::identity().m;
+        for column in 0..4 {
+            // Partial pivoting keeps the elimination stable.
+            let pivot = (column..4)
+                .max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))
+                .unwrap();
+            if a[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(column, pivot);
+            inv.swap(column, pivot);
+
+            let scale = 1.0 / a[column][column];
+            for j in 0..4 {
+                a[column][j] *= scale;
+                inv[column][j] *= scale;
+            }
+            for i in 0..4 {
+                if i == column {
+                    continue;
+                }
+                let factor = a[i][column];
+                for j in 0..4 {
+                    a[i][j] -= factor * a[column][j];
+                    inv[i][j] -= factor * inv[column][j];
+                }
+            }
+        }
+        Some(Mat4 { m: inv })
+    }
+
+    /// Determinant of the upper-left 3x3 block, by which the transform
+    /// scales volumes.
+    pub fn determinant3(&self) -> f64 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    pub fn transform_point(&self, p: Point3) -> Point3 {
+        self.transform_vector(p) + Vec3::new(self.m[0][3], self.m[1][3], self.m[2][3])
+    }
+
+    /// Transforms a direction, which ignores the translation.
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let row = |i: usize| self.m[i][0] * v.x() + self.m[i][1] * v.y() + self.m[i][2] * v.z();
+        Vec3::new(row(0), row(1), row(2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation_test() {
+        let m = Mat4::rotation(Vec3::new(0.0, 1.0, 0.0), 90.0);
+        let v = m.transform_vector(Vec3::new(1.0, 0.0, 0.0));
+        assert!((v - Vec3::new(0.0, 0.0, -1.0)).near_zero());
+
+        let m = Mat4::rotation_xyz(Vec3::new(90.0, 0.0, 90.0));
+        let v = m.transform_vector(Vec3::new(0.0, 1.0, 0.0));
+        assert!((v - Vec3::new(0.0, 0.0, 1.0)).near_zero());
+    }
+
+    #[test]
+    fn translation_test() {
+        let m = Mat4::translation(Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(
+            m.transform_point(Point3::new(1.0, 1.0, 1.0)),
+            Point3::new(2.0, 3.0, 4.0)
+        );
+        assert_eq!(
+            m.transform_vector(Vec3::new(1.0, 1.0, 1.0)),
+            Vec3::new(1.0, 1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn inverse_test() {
+        let m = Mat4::translation(Vec3::new(1.0, -2.0, 3.0))
+            * Mat4::rotation(Vec3::new(1.0, 1.0, 0.0), 30.0)
+            * Mat4::scaling(Vec3::new(2.0, 0.5, 3.0));
+        let product = m * m.inverse().unwrap();
+        for i in 0..4 {
+            for j in 0..4 {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((product[(i, j)] - expected).abs() < 1e-12);
+            }
+        }
+        assert!((m.determinant3() - 3.0).abs() < 1e-12);
+
+        assert!(Mat4::scaling(Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+    }
+
+    #[test]
+    fn look_at_test() {
+        let m = Mat4::look_at(
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 5.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        assert_eq!(m, Mat4::translation(Vec3::new(1.0, 0.0, 0.0)));
+    }
+}
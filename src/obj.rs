@@ -0,0 +1,590 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::objects::dielectric::Dielectric;
+use crate::objects::hittable_list::HitTableList;
+use crate::objects::lambertian::Lambertian;
+use crate::objects::metal::Metal;
+use crate::objects::triangle_mesh::TriangleMesh;
+use crate::objects::MaterialType;
+use crate::vec3::{Color, Point3, Vec3};
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io {
+        path: String,
+        error: std::io::Error,
+    },
+    Parse {
+        file: String,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjError::Io { path, error } => write!(f, "{}: {}", path, error),
+            ObjError::Parse {
+                file,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", file, line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+/// The meshes of an OBJ file, with a warning for each kind of statement the
+/// file or its MTL libraries used that the loader skipped.
+pub struct Model {
+    pub meshes: HitTableList<TriangleMesh>,
+    pub warnings: Vec<String>,
+}
+
+/// The materials of an MTL library, keyed by `newmtl` name, with a warning
+/// for each kind of statement the loader skipped.
+pub struct MaterialLibrary {
+    pub materials: HashMap<String, MaterialType>,
+    pub warnings: Vec<String>,
+}
+
+/// Loads a Wavefront OBJ file, along with any MTL libraries it references, as
+/// one `TriangleMesh` per group and material.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Model, ObjError> {
+    let path = path.as_ref();
+    let source = read(path)?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    parse(&source, &path.display().to_string(), |library| {
+        load_mtl(dir.join(library))
+    })
+}
+
+pub fn load_mtl<P: AsRef<Path>>(path: P) -> Result<MaterialLibrary, ObjError> {
+    let path = path.as_ref();
+    let source = read(path)?;
+    parse_mtl(&source, &path.display().to_string())
+}
+
+fn read(path: &Path) -> Result<String, ObjError> {
+    fs::read_to_string(path).map_err(|error| ObjError::Io {
+        path: path.display().to_string(),
+        error,
+    })
+}
+
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
+struct FaceVertex {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+struct MeshBuilder {
+    material: MaterialType,
+    faces: Vec<[FaceVertex; 3]>,
+}
+
+/// Parses OBJ source. `file` is only used in error messages, and
+/// `load_library` resolves the names given to `mtllib`.
+pub fn parse<F>(source: &str, file: &str, mut load_library: F) -> Result<Model, ObjError>
+where
+    F: FnMut(&str) -> Result<MaterialLibrary, ObjError>,
+{
+    let mut positions: Vec<Point3> = vec![];
+    let mut normals: Vec<Vec3> = vec![];
+    let mut uvs: Vec<(f64, f64)> = vec![];
+    let mut materials: HashMap<String, MaterialType> = HashMap::new();
+
+    let mut builders: Vec<MeshBuilder> = vec![];
+    let mut builder_index: HashMap<(String, String), usize> = HashMap::new();
+    let mut group = String::new();
+    let mut material_name = String::new();
+    let mut material = default_material();
+    let mut current: Option<usize> = None;
+    let mut unsupported = Unsupported::default();
+
+    for (number, line) in source.lines().enumerate() {
+        let line_no = number + 1;
+        let error = |message: String| ObjError::Parse {
+            file: file.to_string(),
+            line: line_no,
+            message,
+        };
+        let line = line.split('#').next().unwrap().trim();
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => {
+                let v = parse_floats(&args, 3, 4).map_err(error)?;
+                positions.push(Point3::new(v[0], v[1], v[2]));
+            }
+            "vn" => {
+                let v = parse_floats(&args, 3, 3).map_err(error)?;
+                normals.push(Vec3::new(v[0], v[1], v[2]));
+            }
+            "vt" => {
+                let v = parse_floats(&args, 1, 3).map_err(error)?;
+                uvs.push((v[0], if v.len() > 1 { v[1] } else { 0.0 }));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(error(format!(
+                        "face needs at least 3 vertices, found {}",
+                        args.len()
+                    )));
+                }
+                let mut polygon = Vec::with_capacity(args.len());
+                for arg in args.iter() {
+                    polygon.push(
+                        parse_face_vertex(arg, positions.len(), uvs.len(), normals.len())
+                            .map_err(error)?,
+                    );
+                }
+                let index = match current {
+                    Some(index) => index,
+                    None => {
+                        let key = (group.clone(), material_name.clone());
+                        let index = *builder_index.entry(key).or_insert_with(|| {
+                            builders.push(MeshBuilder {
+                                material: material.clone(),
+                                faces: vec![],
+                            });
+                            builders.len() - 1
+                        });
+                        current = Some(index);
+                        index
+                    }
+                };
+                for i in 1..polygon.len() - 1 {
+                    builders[index]
+                        .faces
+                        .push([polygon[0], polygon[i], polygon[i + 1]]);
+                }
+            }
+            "g" | "o" => {
+                group = args.join(" ");
+                current = None;
+            }
+            "usemtl" => {
+                if args.len() != 1 {
+                    return Err(error("usemtl expects one material name".to_string()));
+                }
+                material = match materials.get(args[0]) {
+                    Some(material) => material.clone(),
+                    None => return Err(error(format!("unknown material '{}'", args[0]))),
+                };
+                material_name = args[0].to_string();
+                current = None;
+            }
+            "mtllib" => {
+                if args.is_empty() {
+                    return Err(error("mtllib expects a file name".to_string()));
+                }
+                for library in args.iter() {
+                    let library = load_library(library)?;
+                    materials.extend(library.materials);
+                    unsupported.warnings.extend(library.warnings);
+                }
+            }
+            // Smoothing groups, lines, points and free-form geometry are not rendered.
+            "s" | "l" | "p" | "cstype" | "deg" | "curv" | "curv2" | "surf" | "parm" | "end" => {}
+            _ => unsupported.note(file, line_no, keyword),
+        }
+    }
+
+    let mut meshes = HitTableList::new();
+    for builder in builders.into_iter() {
+        meshes.add(build_mesh(&builder, &positions, &uvs, &normals));
+    }
+
+    Ok(Model {
+        meshes,
+        warnings: unsupported.warnings,
+    })
+}
+
+/// Statements this loader does not understand, noted once per keyword and
+/// file instead of rejecting files written by richer exporters.
+#[derive(Default)]
+struct Unsupported {
+    keywords: HashSet<String>,
+    warnings: Vec<String>,
+}
+
+impl Unsupported {
+    fn note(&mut self, file: &str, line: usize, keyword: &str) {
+        if self.keywords.insert(keyword.to_string()) {
+            self.warnings.push(format!(
+                "{}:{}: ignoring unsupported statement '{}'",
+                file, line, keyword
+            ));
+        }
+    }
+}
+
+fn build_mesh(
+    builder: &MeshBuilder,
+    positions: &[Point3],
+    uvs: &[(f64, f64)],
+    normals: &[Vec3],
+) -> TriangleMesh {
+    let corners = || builder.faces.iter().flat_map(|face| face.iter());
+    let has_uvs = corners().all(|corner| corner.uv.is_some());
+    let has_normals = corners().all(|corner| corner.normal.is_some());
+
+    let mut vertex_index: HashMap<FaceVertex, usize> = HashMap::new();
+    let mut mesh_positions = vec![];
+    let mut mesh_uvs = vec![];
+    let mut mesh_normals = vec![];
+    let mut indices = Vec::with_capacity(builder.faces.len());
+
+    for face in builder.faces.iter() {
+        let mut triangle = [0; 3];
+        for (slot, corner) in face.iter().enumerate() {
+            let key = FaceVertex {
+                position: corner.position,
+                uv: if has_uvs { corner.uv } else { None },
+                normal: if has_normals { corner.normal } else { None },
+            };
+            triangle[slot] = *vertex_index.entry(key).or_insert_with(|| {
+                mesh_positions.push(positions[key.position]);
+                if let Some(uv) = key.uv {
+                    mesh_uvs.push(uvs[uv]);
+                }
+                if let Some(normal) = key.normal {
+                    mesh_normals.push(normals[normal]);
+                }
+                mesh_positions.len() - 1
+            });
+        }
+        indices.push(triangle);
+    }
+
+    TriangleMesh::new(
+        mesh_positions,
+        indices,
+        if has_normals {
+            Some(mesh_normals)
+        } else {
+            None
+        },
+        if has_uvs { Some(mesh_uvs) } else { None },
+        builder.material.clone(),
+    )
+}
+
+fn parse_floats(args: &[&str], min: usize, max: usize) -> Result<Vec<f64>, String> {
+    if args.len() < min || args.len() > max {
+        return Err(if min == max {
+            format!("expected {} numbers, found {}", min, args.len())
+        } else {
+            format!("expected {} to {} numbers, found {}", min, max, args.len())
+        });
+    }
+    args.iter()
+        .map(|arg| {
+            arg.parse::<f64>()
+                .map_err(|_| format!("invalid number '{}'", arg))
+        })
+        .collect()
+}
+
+fn parse_face_vertex(
+    arg: &str,
+    position_count: usize,
+    uv_count: usize,
+    normal_count: usize,
+) -> Result<FaceVertex, String> {
+    let mut parts = arg.split('/');
+    let position = resolve_index(parts.next().unwrap_or(""), position_count, "vertex")?;
+    let uv = match parts.next() {
+        Some("") | None => None,
+        Some(part) => Some(resolve_index(part, uv_count, "texture coordinate")?),
+    };
+    let normal = match parts.next() {
+        Some("") | None => None,
+        Some(part) => Some(resolve_index(part, normal_count, "normal")?),
+    };
+    if parts.next().is_some() {
+        return Err(format!("invalid face vertex '{}'", arg));
+    }
+
+    Ok(FaceVertex {
+        position,
+        uv,
+        normal,
+    })
+}
+
+/// Converts a 1-based OBJ index, or a negative index relative to the end, into
+/// a 0-based index.
+fn resolve_index(part: &str, count: usize, kind: &str) -> Result<usize, String> {
+    let index = part
+        .parse::<i64>()
+        .map_err(|_| format!("invalid {} index '{}'", kind, part))?;
+    let resolved = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!(
+            "{} index {} out of range ({} defined)",
+            kind, index, count
+        ));
+    }
+
+    Ok(resolved as usize)
+}
+
+fn default_material() -> MaterialType {
+    MaterialType::Lambertian(Lambertian::new(0.8, 0.8, 0.8))
+}
+
+struct MtlEntry {
+    kd: Color,
+    ks: Color,
+    ns: f64,
+    ni: f64,
+    dissolve: f64,
+    illum: u32,
+}
+
+impl MtlEntry {
+    fn new() -> MtlEntry {
+        MtlEntry {
+            kd: Color::new(0.8, 0.8, 0.8),
+            ks: Color::new(0.0, 0.0, 0.0),
+            ns: 0.0,
+            ni: 1.0,
+            dissolve: 1.0,
+            illum: 2,
+        }
+    }
+
+    /// Maps the MTL parameters onto the closest material the renderer supports.
+    fn material(&self) -> MaterialType {
+        let max = |c: Color| c.x().max(c.y()).max(c.z());
+        let transparent = self.dissolve < 1.0 || matches!(self.illum, 4 | 6 | 7 | 9);
+        let reflective = matches!(self.illum, 3 | 5) || max(self.ks) > max(self.kd);
+
+        if transparent {
+            let ir = if self.ni > 1.0 { self.ni } else { 1.5 };
+            MaterialType::Dielectric(Dielectric::new(ir))
+        } else if reflective && max(self.ks) > 0.0 {
+            let fuzz = (2.0 / (self.ns + 2.0)).sqrt();
+            MaterialType::Metal(Metal::new(self.ks.x(), self.ks.y(), self.ks.z(), fuzz))
+        } else {
+            MaterialType::Lambertian(Lambertian::new(self.kd.x(), self.kd.y(), self.kd.z()))
+        }
+    }
+}
+
+/// Parses MTL source into materials keyed by `newmtl` name.
+pub fn parse_mtl(source: &str, file: &str) -> Result<MaterialLibrary, ObjError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlEntry)> = None;
+    let mut unsupported = Unsupported::default();
+
+    for (number, line) in source.lines().enumerate() {
+        let line_no = number + 1;
+        let error = |message: String| ObjError::Parse {
+            file: file.to_string(),
+            line: line_no,
+            message,
+        };
+        let line = line.split('#').next().unwrap().trim();
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if args.len() != 1 {
+                return Err(error("newmtl expects one material name".to_string()));
+            }
+            if let Some((name, entry)) = current.take() {
+                materials.insert(name, entry.material());
+            }
+            current = Some((args[0].to_string(), MtlEntry::new()));
+            continue;
+        }
+
+        let entry = match current.as_mut() {
+            Some((_, entry)) => entry,
+            None => return Err(error(format!("'{}' before any newmtl", keyword))),
+        };
+        match keyword {
+            "Kd" => {
+                let v = parse_floats(&args, 3, 3).map_err(error)?;
+                entry.kd = Color::new(v[0], v[1], v[2]);
+            }
+            "Ks" => {
+                let v = parse_floats(&args, 3, 3).map_err(error)?;
+                entry.ks = Color::new(v[0], v[1], v[2]);
+            }
+            "Ns" => entry.ns = parse_floats(&args, 1, 1).map_err(error)?[0],
+            "Ni" => entry.ni = parse_floats(&args, 1, 1).map_err(error)?[0],
+            "d" => entry.dissolve = parse_floats(&args, 1, 1).map_err(error)?[0],
+            "Tr" => entry.dissolve = 1.0 - parse_floats(&args, 1, 1).map_err(error)?[0],
+            "illum" => {
+                entry.illum = match args.first().and_then(|arg| arg.parse::<u32>().ok()) {
+                    Some(illum) if args.len() == 1 => illum,
+                    _ => return Err(error("illum expects one integer".to_string())),
+                }
+            }
+            // Ambient and emissive colours, filters and texture maps have no equivalent yet.
+            "Ka" | "Ke" | "Tf" | "sharpness" => {}
+            _ if keyword.starts_with("map_") || keyword == "bump" || keyword == "disp" => {}
+            _ => unsupported.note(file, line_no, keyword),
+        }
+    }
+    if let Some((name, entry)) = current.take() {
+        materials.insert(name, entry.material());
+    }
+
+    Ok(MaterialLibrary {
+        materials,
+        warnings: unsupported.warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ray::Ray;
+    use crate::core::{HitRecord, HitTable};
+
+    const QUAD: &str = "
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        vn 0 0 1
+        g square
+        f 1//1 2//1 3//1 4//1
+        g corner
+        f -4 -3 -1
+    ";
+
+    fn no_library(_: &str) -> Result<MaterialLibrary, ObjError> {
+        Ok(MaterialLibrary {
+            materials: HashMap::new(),
+            warnings: vec![],
+        })
+    }
+
+    #[test]
+    fn parse_triangulates_polygons_test() {
+        let model = parse(QUAD, "quad.obj", no_library).unwrap();
+        let meshes = model.meshes.into_objects();
+
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(meshes[0].len(), 2);
+        assert_eq!(meshes[1].len(), 1);
+    }
+
+    #[test]
+    fn parse_hits_geometry_test() {
+        let world = parse(QUAD, "quad.obj", no_library).unwrap().meshes;
+        let ray = Ray::new(Point3::new(0.75, 0.75, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::default();
+
+        assert!(world.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 1.0);
+    }
+
+    #[test]
+    fn parse_reports_line_numbers_test() {
+        let source = "v 0 0 0\nv 1 0 0\nf 1 2 3\n";
+        let err = parse(source, "bad.obj", no_library).err().unwrap();
+
+        assert_eq!(
+            err.to_string(),
+            "bad.obj:3: vertex index 3 out of range (2 defined)"
+        );
+    }
+
+    #[test]
+    fn parse_skips_unsupported_statements_test() {
+        let source =
+            "vp 0.5 0.5\nmg 1 2\nmaplib lib.map\nusemap none\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+        let model = parse(source, "extra.obj", no_library).unwrap();
+        assert_eq!(
+            model.warnings,
+            [
+                "extra.obj:1: ignoring unsupported statement 'vp'",
+                "extra.obj:2: ignoring unsupported statement 'mg'",
+                "extra.obj:3: ignoring unsupported statement 'maplib'",
+                "extra.obj:4: ignoring unsupported statement 'usemap'",
+            ]
+        );
+        assert_eq!(model.meshes.into_objects()[0].len(), 1);
+
+        let err = parse("vp 0.5\nv 0 zero 0\n", "bad.obj", no_library)
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with("bad.obj:2: "));
+    }
+
+    #[test]
+    fn parse_mtl_skips_unsupported_keys_test() {
+        let source = "newmtl pbr\nKd 0.5 0.5 0.5\nPr 0.4\nPm 1.0\nKe 1 1 1\nPr 0.2\n";
+        let library = parse_mtl(source, "pbr.mtl").unwrap();
+        assert!(matches!(
+            library.materials["pbr"],
+            MaterialType::Lambertian(_)
+        ));
+        assert_eq!(
+            library.warnings,
+            [
+                "pbr.mtl:3: ignoring unsupported statement 'Pr'",
+                "pbr.mtl:4: ignoring unsupported statement 'Pm'",
+            ]
+        );
+
+        // Warnings from a library are reported with those of the OBJ file.
+        let model = parse("mtllib pbr.mtl\nv 0 0 0\n", "pbr.obj", |library| {
+            parse_mtl(source, library)
+        })
+        .unwrap();
+        assert_eq!(model.warnings, library.warnings);
+
+        let err = parse_mtl("newmtl pbr\nPr 0.4\nNs shiny\n", "bad.mtl")
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with("bad.mtl:3: "));
+    }
+
+    #[test]
+    fn parse_mtl_maps_materials_test() {
+        let source = "
+            newmtl glass
+            Ni 1.5
+            d 0.1
+            newmtl chrome
+            Kd 0.1 0.1 0.1
+            Ks 0.9 0.9 0.9
+            Ns 500
+            newmtl clay
+            Kd 0.6 0.3 0.2
+        ";
+        let materials = parse_mtl(source, "test.mtl").unwrap().materials;
+
+        assert!(matches!(materials["glass"], MaterialType::Dielectric(_)));
+        assert!(matches!(materials["chrome"], MaterialType::Metal(_)));
+        assert!(matches!(materials["clay"], MaterialType::Lambertian(_)));
+    }
+}
@@ -0,0 +1,152 @@
+use crate::core::aabb::Aabb;
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, HitTable};
+use crate::objects::MaterialType;
+use crate::vec3::{Point3, Vec3};
+
+/// Solid box with faces perpendicular to the axes, intersected with the slab
+/// test in one go rather than as six quads. Each face is textured with `u`
+/// and `v` running from 0 to 1 along the next two axes in x, y, z order.
+pub struct AxisAlignedBox {
+    bounds: Aabb,
+    material: MaterialType,
+}
+
+impl AxisAlignedBox {
+    /// The box with opposite corners `a` and `b`.
+    pub fn new(a: Point3, b: Point3, material: MaterialType) -> Self {
+        AxisAlignedBox {
+            bounds: Aabb::new(a, b),
+            material,
+        }
+    }
+}
+
+impl AxisAlignedBox {
+    /// Where `ray` enters and leaves the box, and through which axis, if it
+    /// meets it at all.
+    fn crossings(&self, ray: &Ray) -> Option<((f64, usize), (f64, usize))> {
+        let (min, max) = (self.bounds.min(), self.bounds.max());
+        let (origin, direction) = (ray.origin(), ray.direction());
+        let (mut t_enter, mut enter_axis) = (f64::NEG_INFINITY, 0);
+        let (mut t_exit, mut exit_axis) = (f64::INFINITY, 0);
+        for axis in 0..3 {
+            let inv_d = 1.0 / direction[axis];
+            let mut t0 = (min[axis] - origin[axis]) * inv_d;
+            let mut t1 = (max[axis] - origin[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            if t0 > t_enter {
+                t_enter = t0;
+                enter_axis = axis;
+            }
+            if t1 < t_exit {
+                t_exit = t1;
+                exit_axis = axis;
+            }
+        }
+        if t_exit < t_enter {
+            return None;
+        }
+        Some(((t_enter, enter_axis), (t_exit, exit_axis)))
+    }
+
+    /// Fills in `rec` for the crossing at `t` through a face perpendicular
+    /// to `axis`, whose outward normal points along the axis when `sign` is
+    /// positive.
+    fn record<'a>(&'a self, ray: &Ray, t: f64, axis: usize, sign: f64, rec: &mut HitRecord<'a>) {
+        let (min, max) = (self.bounds.min(), self.bounds.max());
+        rec.t = t;
+        rec.p = ray.at(t);
+        let mut n = [0.0; 3];
+        n[axis] = sign;
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        rec.u = (rec.p[a] - min[a]) / (max[a] - min[a]);
+        rec.v = (rec.p[b] - min[b]) / (max[b] - min[b]);
+        rec.material = &self.material;
+        rec.set_face_normal(ray, Vec3::new(n[0], n[1], n[2]));
+    }
+}
+
+impl HitTable for AxisAlignedBox {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let ((t_enter, enter_axis), (t_exit, exit_axis)) = match self.crossings(ray) {
+            Some(crossings) => crossings,
+            None => return false,
+        };
+        let direction = ray.direction();
+
+        // The outward normal faces against the ray where it enters and with
+        // it where it leaves.
+        if t_enter >= t_min && t_enter <= t_max {
+            self.record(
+                ray,
+                t_enter,
+                enter_axis,
+                -direction[enter_axis].signum(),
+                rec,
+            );
+        } else if t_exit >= t_min && t_exit <= t_max {
+            self.record(ray, t_exit, exit_axis, direction[exit_axis].signum(), rec);
+        } else {
+            return false;
+        }
+
+        true
+    }
+
+    fn hit_all<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, hits: &mut Vec<HitRecord<'a>>) {
+        if let Some(((t_enter, enter_axis), (t_exit, exit_axis))) = self.crossings(ray) {
+            let direction = ray.direction();
+            for (t, axis, sign) in [
+                (t_enter, enter_axis, -direction[enter_axis].signum()),
+                (t_exit, exit_axis, direction[exit_axis].signum()),
+            ] {
+                if t_min <= t && t <= t_max {
+                    let mut rec = HitRecord::default();
+                    self.record(ray, t, axis, sign, &mut rec);
+                    hits.push(rec);
+                }
+            }
+        }
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = self.bounds;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn axis_aligned_box_test() {
+        let cube = AxisAlignedBox::new(
+            Point3::new(1.0, 1.0, 1.0),
+            Point3::new(-1.0, -1.0, -1.0),
+            MaterialType::None,
+        );
+        let mut rec = HitRecord::default();
+
+        let toward = Ray::new(Point3::new(0.5, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(cube.hit(&toward, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 4.0);
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, -1.0));
+        assert!(rec.front_face);
+        assert_eq!((rec.u, rec.v), (0.75, 0.5));
+
+        // From inside, the far face is hit from behind.
+        let inside = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0));
+        assert!(cube.hit(&inside, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 0.5);
+        assert_eq!(rec.normal, Vec3::new(0.0, -1.0, 0.0));
+        assert!(!rec.front_face);
+
+        let beside = Ray::new(Point3::new(2.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(!cube.hit(&beside, 0.001, f64::INFINITY, &mut rec));
+        assert!(!cube.hit(&toward, 0.001, 3.0, &mut rec));
+    }
+}
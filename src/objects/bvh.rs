@@ -0,0 +1,380 @@
+use std::sync::Arc;
+
+use crate::core::aabb::Aabb;
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, HitTable};
+use crate::objects::hittable_list::HitTableList;
+
+const SAH_BUCKETS: usize = 12;
+
+/// Bounding volume hierarchy over a set of hittables.
+///
+/// Children are split with a binned surface area heuristic along the longest
+/// axis of the primitive centroids, falling back to a median split when the
+/// centroids coincide. Objects without a bounding box, such as infinite
+/// planes, are kept out of the hierarchy and tested against every ray.
+pub struct BvhNode {
+    left: Arc<dyn HitTable>,
+    right: Option<Arc<dyn HitTable>>,
+    bbox: Aabb,
+    /// Unbounded objects, only ever held by the root.
+    unbounded: Vec<Arc<dyn HitTable>>,
+}
+
+struct Primitive {
+    object: Arc<dyn HitTable>,
+    bbox: Aabb,
+    centroid: f64,
+}
+
+impl BvhNode {
+    pub fn new<T>(list: HitTableList<T>) -> BvhNode
+    where
+        T: HitTable + 'static,
+    {
+        BvhNode::from_objects(
+            list.into_objects()
+                .into_iter()
+                .map(|object| Arc::new(object) as Arc<dyn HitTable>)
+                .collect(),
+        )
+    }
+
+    pub fn from_objects(objects: Vec<Arc<dyn HitTable>>) -> BvhNode {
+        let mut primitives = Vec::new();
+        let mut unbounded = Vec::new();
+        for object in objects {
+            let mut bbox = Aabb::default();
+            if object.bounding_box(&mut bbox) {
+                primitives.push(Primitive {
+                    object,
+                    bbox,
+                    centroid: 0.0,
+                });
+            } else {
+                unbounded.push(object);
+            }
+        }
+
+        let mut root = if primitives.is_empty() {
+            // No objects, or only unbounded ones: an empty box around an
+            // empty list, which no ray hits.
+            BvhNode {
+                left: Arc::new(HitTableList::<Arc<dyn HitTable>>::new()),
+                right: None,
+                bbox: Aabb::default(),
+                unbounded: Vec::new(),
+            }
+        } else {
+            BvhNode::build(primitives)
+        };
+        root.unbounded = unbounded;
+        root
+    }
+
+    fn build(mut primitives: Vec<Primitive>) -> BvhNode {
+        let bbox = primitives
+            .iter()
+            .skip(1)
+            .fold(primitives[0].bbox, |acc, p| {
+                Aabb::surrounding_box(&acc, &p.bbox)
+            });
+
+        match primitives.len() {
+            1 => {
+                return BvhNode {
+                    left: primitives.pop().unwrap().object,
+                    right: None,
+                    bbox,
+                    unbounded: Vec::new(),
+                }
+            }
+            2 => {
+                let right = primitives.pop().unwrap().object;
+                let left = primitives.pop().unwrap().object;
+                return BvhNode {
+                    left,
+                    right: Some(right),
+                    bbox,
+                    unbounded: Vec::new(),
+                };
+            }
+            _ => {}
+        }
+
+        let first = primitives[0].bbox.centroid();
+        let centroid_bounds = primitives
+            .iter()
+            .skip(1)
+            .fold(Aabb::new(first, first), |acc, p| {
+                let c = p.bbox.centroid();
+                Aabb::surrounding_box(&acc, &Aabb::new(c, c))
+            });
+        let axis = centroid_bounds.longest_axis();
+        let c_min = centroid_bounds.min()[axis];
+        let c_max = centroid_bounds.max()[axis];
+        for p in primitives.iter_mut() {
+            p.centroid = p.bbox.centroid()[axis];
+        }
+        primitives.sort_by(|a, b| a.centroid.total_cmp(&b.centroid));
+
+        let mid = if c_max - c_min > 0.0 {
+            BvhNode::sah_split(&primitives, c_min, c_max)
+        } else {
+            primitives.len() / 2
+        };
+
+        let right_half = primitives.split_off(mid);
+        let left = BvhNode::child(primitives);
+        let right = BvhNode::child(right_half);
+        BvhNode {
+            left,
+            right: Some(right),
+            bbox,
+            unbounded: Vec::new(),
+        }
+    }
+
+    /// Finds the closest of the node's objects that `hit` reports along
+    /// `ray`, testing the unbounded ones first so that their hits can cull
+    /// the hierarchy.
+    fn closest_hit<'a>(
+        &'a self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord<'a>,
+        hit: for<'b> fn(&'b dyn HitTable, &Ray, f64, f64, &mut HitRecord<'b>) -> bool,
+    ) -> bool {
+        let mut hit_anything = false;
+        let mut closest_so_far = t_max;
+        for object in &self.unbounded {
+            if hit(object.as_ref(), ray, t_min, closest_so_far, rec) {
+                hit_anything = true;
+                closest_so_far = rec.t;
+            }
+        }
+        if !self.bbox.hit(ray, t_min, closest_so_far) {
+            return hit_anything;
+        }
+
+        if hit(self.left.as_ref(), ray, t_min, closest_so_far, rec) {
+            hit_anything = true;
+            closest_so_far = rec.t;
+        }
+        if let Some(right) = &self.right {
+            if hit(right.as_ref(), ray, t_min, closest_so_far, rec) {
+                hit_anything = true;
+            }
+        }
+        hit_anything
+    }
+
+    fn child(mut primitives: Vec<Primitive>) -> Arc<dyn HitTable> {
+        if primitives.len() == 1 {
+            primitives.pop().unwrap().object
+        } else {
+            Arc::new(BvhNode::build(primitives))
+        }
+    }
+
+    /// Returns the split index into `primitives`, which must be sorted by centroid.
+    fn sah_split(primitives: &[Primitive], c_min: f64, c_max: f64) -> usize {
+        let bucket_of = |c: f64| {
+            let b = ((c - c_min) / (c_max - c_min) * SAH_BUCKETS as f64) as usize;
+            b.min(SAH_BUCKETS - 1)
+        };
+        let mut counts = [0usize; SAH_BUCKETS];
+        let mut bounds: [Option<Aabb>; SAH_BUCKETS] = [None; SAH_BUCKETS];
+        for p in primitives {
+            let b = bucket_of(p.centroid);
+            counts[b] += 1;
+            bounds[b] = Some(match bounds[b] {
+                Some(bbox) => Aabb::surrounding_box(&bbox, &p.bbox),
+                None => p.bbox,
+            });
+        }
+
+        let mut best_cost = f64::INFINITY;
+        let mut best_bucket = 0;
+        for split in 0..SAH_BUCKETS - 1 {
+            let (left_count, left_area) =
+                BvhNode::bucket_cost(&counts[..=split], &bounds[..=split]);
+            let (right_count, right_area) =
+                BvhNode::bucket_cost(&counts[split + 1..], &bounds[split + 1..]);
+            if left_count == 0 || right_count == 0 {
+                continue;
+            }
+            let cost = left_count as f64 * left_area + right_count as f64 * right_area;
+            if cost < best_cost {
+                best_cost = cost;
+                best_bucket = split;
+            }
+        }
+
+        let mid = primitives
+            .iter()
+            .position(|p| bucket_of(p.centroid) > best_bucket)
+            .unwrap_or(primitives.len());
+        if mid == 0 || mid == primitives.len() {
+            primitives.len() / 2
+        } else {
+            mid
+        }
+    }
+
+    fn bucket_cost(counts: &[usize], bounds: &[Option<Aabb>]) -> (usize, f64) {
+        let count = counts.iter().sum();
+        let area = bounds
+            .iter()
+            .flatten()
+            .copied()
+            .reduce(|a, b| Aabb::surrounding_box(&a, &b))
+            .map_or(0.0, |bbox| bbox.surface_area());
+        (count, area)
+    }
+}
+
+impl HitTable for BvhNode {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        self.closest_hit(ray, t_min, t_max, rec, |object, ray, t_min, t_max, rec| {
+            object.hit(ray, t_min, t_max, rec)
+        })
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = self.bbox;
+        self.unbounded.is_empty()
+    }
+
+    fn hit_surface<'a>(
+        &'a self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord<'a>,
+    ) -> bool {
+        self.closest_hit(ray, t_min, t_max, rec, |object, ray, t_min, t_max, rec| {
+            object.hit_surface(ray, t_min, t_max, rec)
+        })
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let unbounded: f64 = self
+            .unbounded
+            .iter()
+            .map(|object| object.transmittance(ray, t_min, t_max))
+            .product();
+        if !self.bbox.hit(ray, t_min, t_max) {
+            return unbounded;
+        }
+        let right = match &self.right {
+            Some(right) => right.transmittance(ray, t_min, t_max),
+            None => 1.0,
+        };
+        unbounded * self.left.transmittance(ray, t_min, t_max) * right
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::plane::Plane;
+    use crate::objects::sphere::Sphere;
+    use crate::objects::MaterialType;
+    use crate::vec3::{Point3, Vec3};
+
+    #[test]
+    fn matches_linear_list_test() {
+        let mut list = HitTableList::new();
+        let mut spheres = HitTableList::new();
+        for i in 0..40 {
+            let (x, z) = ((i % 7) as f64 - 3.0, (i / 7) as f64 - 3.0);
+            list.add(Sphere::new(x, 0.0, z, 0.3, MaterialType::None));
+            spheres.add(Sphere::new(x, 0.0, z, 0.3, MaterialType::None));
+        }
+        let bvh = BvhNode::new(spheres);
+
+        for i in 0..100 {
+            let origin = Point3::new(-5.0 + 0.1 * i as f64, 5.0, -5.0);
+            let ray = Ray::new(origin, Vec3::new(0.3, -1.0, 0.4));
+            let mut expected = HitRecord::default();
+            let mut actual = HitRecord::default();
+            let hit = list.hit(&ray, 0.001, f64::INFINITY, &mut expected);
+
+            assert_eq!(bvh.hit(&ray, 0.001, f64::INFINITY, &mut actual), hit);
+            if hit {
+                assert_eq!(actual.t, expected.t);
+            }
+        }
+    }
+
+    #[test]
+    fn nan_centroid_test() {
+        let mut spheres = HitTableList::new();
+        for i in 0..8 {
+            spheres.add(Sphere::new(i as f64, 0.0, 0.0, 0.3, MaterialType::None));
+        }
+        spheres.add(Sphere::new(f64::NAN, 0.0, 0.0, 0.3, MaterialType::None));
+        let bvh = BvhNode::new(spheres);
+
+        let ray = Ray::new(Point3::new(3.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let mut rec = HitRecord::default();
+        assert!(bvh.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert!((rec.t - 4.7).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unbounded_objects_test() {
+        let mut list: HitTableList<Box<dyn HitTable>> = HitTableList::new();
+        list.add(Box::new(Plane::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            MaterialType::None,
+        )));
+        list.add(Box::new(Sphere::new(
+            0.0,
+            1.0,
+            0.0,
+            0.5,
+            MaterialType::None,
+        )));
+        let bvh = BvhNode::new(list);
+        assert!(!bvh.bounding_box(&mut Aabb::default()));
+
+        let down = Vec3::new(0.0, -1.0, 0.0);
+        let mut rec = HitRecord::default();
+        let onto_sphere = Ray::new(Point3::new(0.0, 5.0, 0.0), down);
+        assert!(bvh.hit(&onto_sphere, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 3.5);
+        let onto_plane = Ray::new(Point3::new(3.0, 5.0, 0.0), down);
+        assert!(bvh.hit(&onto_plane, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 5.0);
+
+        let mut planes = HitTableList::new();
+        planes.add(Plane::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            MaterialType::None,
+        ));
+        let only_plane = BvhNode::new(planes);
+        assert!(only_plane.hit(&onto_plane, 0.001, f64::INFINITY, &mut rec));
+        assert!(!only_plane.hit(
+            &Ray::new(Point3::new(0.0, 5.0, 0.0), -down),
+            0.001,
+            f64::INFINITY,
+            &mut rec
+        ));
+    }
+
+    #[test]
+    fn empty_test() {
+        let bvh = BvhNode::from_objects(vec![]);
+
+        // Through the origin, where the empty bounding box sits.
+        let ray = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let mut rec = HitRecord::default();
+        assert!(!bvh.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(bvh.transmittance(&ray, 0.001, f64::INFINITY), 1.0);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::core::aabb::Aabb;
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, HitTable};
+use crate::objects::quadric::{first_root, solve_quadratic, Extent, SurfaceHit};
+use crate::objects::MaterialType;
+use crate::vec3::{Point3, Vec3};
+
+/// Cone with a base of `radius` on `center`, narrowing to its apex `height`
+/// above it along the y axis. Cut below the apex it becomes a frustum, with
+/// flat caps on both ends unless `uncapped`.
+pub struct Cone {
+    center: Point3,
+    radius: f64,
+    height: f64,
+    extent: Extent,
+    material: MaterialType,
+}
+
+impl Cone {
+    pub fn new(center: Point3, radius: f64, height: f64, material: MaterialType) -> Self {
+        Cone {
+            center,
+            radius,
+            height,
+            extent: Extent::new(0.0, height),
+            material,
+        }
+    }
+
+    /// Keeps only the part between heights `y_min` and `y_max` above the
+    /// base.
+    pub fn with_height_limits(mut self, y_min: f64, y_max: f64) -> Self {
+        self.extent = self.extent.clip(y_min, y_max);
+        self
+    }
+
+    /// Keeps only `degrees` of the turn around the axis, starting from +x.
+    pub fn with_sweep(mut self, degrees: f64) -> Self {
+        self.extent = self.extent.sweep(degrees);
+        self
+    }
+
+    /// Leaves the ends open.
+    pub fn uncapped(mut self) -> Self {
+        self.extent.capped = false;
+        self
+    }
+
+    /// Radius of the cone at height `y`.
+    fn radius_at(&self, y: f64) -> f64 {
+        self.radius * (self.height - y) / self.height
+    }
+}
+
+impl HitTable for Cone {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        // x^2 + z^2 = k^2 (h - y)^2, with w the height of the apex above the
+        // ray origin.
+        let (o, d) = (ray.origin() - self.center, ray.direction());
+        let k2 = (self.radius / self.height).powi(2);
+        let w = self.height - o.y();
+        let a = d.x() * d.x() + d.z() * d.z() - k2 * d.y() * d.y();
+        let b = 2.0 * (o.x() * d.x() + o.z() * d.z() + k2 * w * d.y());
+        let c = o.x() * o.x() + o.z() * o.z() - k2 * w * w;
+        // The height limits also keep out the mirror cone above the apex.
+        let side = first_root(&solve_quadratic(a, b, c), t_min, t_max, |t| {
+            let p = o + t * d;
+            let phi = self.extent.contains(p)?;
+            let (u, v) = self.extent.side_uv(phi, p.y());
+            Some(SurfaceHit {
+                t,
+                p,
+                normal: Vec3::new(p.x(), k2 * (self.height - p.y()), p.z()),
+                u,
+                v,
+            })
+        });
+        let caps = self
+            .extent
+            .hit_caps(o, d, |y| self.radius_at(y), t_min, t_max);
+
+        match SurfaceHit::nearest(side, caps) {
+            Some(hit) => {
+                hit.record(ray, self.center, &self.material, rec);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let r = self.radius_at(self.extent.y_min);
+        *output_box = Aabb::new(
+            self.center + Vec3::new(-r, self.extent.y_min, -r),
+            self.center + Vec3::new(r, self.extent.y_max, r),
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cone_test() {
+        let cone = Cone::new(Point3::new(0.0, 0.0, 0.0), 1.0, 2.0, MaterialType::None);
+        let mut rec = HitRecord::default();
+
+        // Halfway up the radius is 0.5, and the side leans back by 1 in 2.
+        let side = Ray::new(Point3::new(-5.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(cone.hit(&side, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 4.5);
+        assert!((rec.normal - Vec3::new(-2.0, 1.0, 0.0).unit_vector()).near_zero());
+
+        let up = Ray::new(Point3::new(0.5, -5.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert!(cone.hit(&up, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 5.0);
+        assert_eq!(rec.normal, Vec3::new(0.0, -1.0, 0.0));
+
+        // Above the apex lies only the mirror cone, which is not part of it.
+        let above = Ray::new(Point3::new(-5.0, 3.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(!cone.hit(&above, 0.001, f64::INFINITY, &mut rec));
+
+        // A frustum gets a cap on top.
+        let frustum = Cone::new(Point3::new(0.0, 0.0, 0.0), 1.0, 2.0, MaterialType::None)
+            .with_height_limits(0.0, 1.0);
+        let down = Ray::new(Point3::new(0.25, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(frustum.hit(&down, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 4.0);
+        assert_eq!(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+
+        let mut bbox = Aabb::default();
+        assert!(frustum.bounding_box(&mut bbox));
+        assert_eq!(bbox.max(), Point3::new(1.0, 1.0, 1.0));
+    }
+}
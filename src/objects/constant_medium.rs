@@ -0,0 +1,158 @@
+use crate::core::aabb::Aabb;
+use crate::core::ray::Ray;
+use crate::core::rng::Rng;
+use crate::core::{HitRecord, HitTable};
+use crate::objects::isotropic::Isotropic;
+use crate::objects::MaterialType;
+use crate::samplers::hash;
+use crate::textures::TextureType;
+use crate::vec3::Vec3;
+
+/// Smoke or fog of constant density filling a closed `boundary`. A ray
+/// passing through is scattered at a random distance with probability
+/// growing with the length of its path inside, and goes straight through
+/// otherwise.
+pub struct ConstantMedium<T: HitTable> {
+    boundary: T,
+    density: f64,
+    phase_function: MaterialType,
+}
+
+impl<T: HitTable> ConstantMedium<T> {
+    /// A medium scattering light equally in all directions.
+    pub fn new(boundary: T, density: f64, albedo: TextureType) -> Self {
+        ConstantMedium::with_phase_function(
+            boundary,
+            density,
+            MaterialType::Isotropic(Isotropic::with_texture(albedo)),
+        )
+    }
+
+    pub fn with_phase_function(boundary: T, density: f64, phase_function: MaterialType) -> Self {
+        ConstantMedium {
+            boundary,
+            density,
+            phase_function,
+        }
+    }
+
+    /// Range of `t` within [`t_min`, `t_max`] where `ray` is inside the
+    /// boundary, along with where it first enters the boundary.
+    fn interval(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64, f64)> {
+        let mut rec1 = HitRecord::default();
+        let mut rec2 = HitRecord::default();
+        if !self
+            .boundary
+            .hit(ray, f64::NEG_INFINITY, f64::INFINITY, &mut rec1)
+        {
+            return None;
+        }
+        if !self
+            .boundary
+            .hit(ray, rec1.t + 0.0001, f64::INFINITY, &mut rec2)
+        {
+            return None;
+        }
+
+        let t_enter = rec1.t.max(t_min).max(0.0);
+        let t_exit = rec2.t.min(t_max);
+        if t_enter >= t_exit {
+            return None;
+        }
+        Some((t_enter, t_exit, rec1.t))
+    }
+}
+
+impl<T: HitTable> HitTable for ConstantMedium<T> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let (t_enter, t_exit, entry) = match self.interval(ray, t_min, t_max) {
+            Some(interval) => interval,
+            None => return false,
+        };
+
+        let ray_length = ray.direction().length();
+        let distance_inside_boundary = (t_exit - t_enter) * ray_length;
+        let hit_distance = -(1.0 - ray_rng(ray, entry).f64()).ln() / self.density;
+        if hit_distance > distance_inside_boundary {
+            return false;
+        }
+
+        rec.t = t_enter + hit_distance / ray_length;
+        rec.p = ray.at(rec.t);
+        // Arbitrary, a medium has no surface.
+        rec.normal = Vec3::new(1.0, 0.0, 0.0);
+        rec.front_face = true;
+        rec.material = &self.phase_function;
+
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        self.boundary.bounding_box(output_box)
+    }
+
+    fn hit_surface<'a>(
+        &'a self,
+        _ray: &Ray,
+        _t_min: f64,
+        _t_max: f64,
+        _rec: &mut HitRecord<'a>,
+    ) -> bool {
+        false
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        match self.interval(ray, t_min, t_max) {
+            Some((t_enter, t_exit, _)) => {
+                (-self.density * (t_exit - t_enter) * ray.direction().length()).exp()
+            }
+            None => 1.0,
+        }
+    }
+}
+
+/// Random numbers for a medium's tracking along `ray`, hashed from the ray
+/// since `hit` is given no sampler. The same ray always gives the same
+/// numbers, so a medium makes the same decision each time the ray is tested
+/// against it, and `salt`, such as where the ray enters the medium, keeps
+/// overlapping media apart. The numbers are only as independent as the rays
+/// are distinct: samples that trace bit-identical rays, such as camera rays
+/// through the same point of a pixel, scatter at the same distances and add
+/// no variety to the estimate.
+pub(crate) fn ray_rng(ray: &Ray, salt: f64) -> Rng {
+    let (o, d) = (ray.origin(), ray.direction());
+    let bits = [o.x(), o.y(), o.z(), d.x(), d.y(), d.z(), ray.time(), salt].map(f64::to_bits);
+    Rng::new(hash(&bits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::sphere::Sphere;
+    use crate::vec3::{Color, Point3};
+
+    #[test]
+    fn constant_medium_test() {
+        let boundary = Sphere::new(0.0, 0.0, 0.0, 1.0, MaterialType::None);
+        let medium =
+            ConstantMedium::new(boundary, 1.0, TextureType::from(Color::new(1.0, 1.0, 1.0)));
+
+        // The chance of scattering across the diameter is 1 - e^-2.
+        let mut scattered = 0;
+        for i in 0..10000 {
+            let origin = Point3::new(i as f64 * 1e-9, 0.0, -5.0);
+            let ray = Ray::new(origin, Vec3::new(0.0, 0.0, 1.0));
+            let mut rec = HitRecord::default();
+            if medium.hit(&ray, 0.001, f64::INFINITY, &mut rec) {
+                assert!(rec.t > 4.0 && rec.t < 6.0);
+                scattered += 1;
+            }
+        }
+        let expected = 10000.0 * (1.0 - (-2.0f64).exp());
+        assert!((scattered as f64 - expected).abs() < 200.0, "{}", scattered);
+
+        let ray = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        let transmittance = medium.transmittance(&ray, 0.001, 5.5);
+        assert!((transmittance - (-1.5f64).exp()).abs() < 1e-3);
+    }
+}
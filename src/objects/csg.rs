@@ -0,0 +1,348 @@
+use crate::core::aabb::Aabb;
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, HitTable};
+use crate::vec3::Vec3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsgOperation {
+    /// Inside either operand.
+    Union,
+    /// Inside both operands.
+    Intersection,
+    /// Inside the first operand but not the second.
+    Difference,
+}
+
+impl CsgOperation {
+    fn contains(self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            CsgOperation::Union => in_a || in_b,
+            CsgOperation::Intersection => in_a && in_b,
+            CsgOperation::Difference => in_a && !in_b,
+        }
+    }
+}
+
+/// Solid combined from two closed objects by a union, intersection or
+/// difference. Every crossing of each operand along the whole line of a
+/// ray is found with `hit_all`, and walked in order to see where the ray
+/// passes in and out of the result. Each surface keeps the material and
+/// texture coordinates of the operand it comes from; where the second
+/// operand is cut out of the first its normal is turned to face into it.
+///
+/// Operands must be closed, or the walk loses track of which side of them
+/// the ray is on. Combinations can be nested, since they enumerate their
+/// own crossings in turn.
+pub struct Csg<A, B> {
+    a: A,
+    b: B,
+    operation: CsgOperation,
+    /// Boxes of the operands, `None` when unbounded.
+    a_box: Option<Aabb>,
+    b_box: Option<Aabb>,
+}
+
+impl<A: HitTable, B: HitTable> Csg<A, B> {
+    pub fn new(operation: CsgOperation, a: A, b: B) -> Self {
+        let bounds = |object: &dyn HitTable| {
+            let mut bbox = Aabb::default();
+            if object.bounding_box(&mut bbox) {
+                Some(bbox)
+            } else {
+                None
+            }
+        };
+        Csg {
+            a_box: bounds(&a),
+            b_box: bounds(&b),
+            a,
+            b,
+            operation,
+        }
+    }
+
+    pub fn union(a: A, b: B) -> Self {
+        Csg::new(CsgOperation::Union, a, b)
+    }
+
+    pub fn intersection(a: A, b: B) -> Self {
+        Csg::new(CsgOperation::Intersection, a, b)
+    }
+
+    /// `a` with `b` cut out of it.
+    pub fn difference(a: A, b: B) -> Self {
+        Csg::new(CsgOperation::Difference, a, b)
+    }
+
+    /// Passes the crossings of the result's surface anywhere along the line
+    /// of `ray` to `visit`, nearest first, until it returns false.
+    fn crossings<'a, F>(&'a self, ray: &Ray, mut visit: F)
+    where
+        F: FnMut(HitRecord<'a>) -> bool,
+    {
+        // Nothing is left of a solid the ray misses in an intersection or
+        // difference.
+        let needs_a = self.operation != CsgOperation::Union;
+        let needs_b = self.operation == CsgOperation::Intersection;
+        if (needs_a && !may_hit(self.a_box, ray)) || (needs_b && !may_hit(self.b_box, ray)) {
+            return;
+        }
+
+        // One buffer holds the crossings of `a` followed by those of `b`.
+        let mut hits = Vec::new();
+        self.a
+            .hit_all(ray, f64::NEG_INFINITY, f64::INFINITY, &mut hits);
+        if hits.is_empty() && needs_a {
+            return;
+        }
+        let a_count = hits.len();
+        self.b
+            .hit_all(ray, f64::NEG_INFINITY, f64::INFINITY, &mut hits);
+
+        // Far along the line behind the ray, it is outside everything.
+        let (mut in_a, mut in_b, mut inside) = (false, false, false);
+        let (a_hits, b_hits) = hits.split_at(a_count);
+        let (mut a_hits, mut b_hits) = (
+            a_hits.iter().copied().peekable(),
+            b_hits.iter().copied().peekable(),
+        );
+        loop {
+            let from_a = match (a_hits.peek(), b_hits.peek()) {
+                (Some(a), Some(b)) => a.t <= b.t,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => break,
+            };
+            let mut rec = if from_a { a_hits.next() } else { b_hits.next() }.unwrap();
+            if from_a {
+                in_a = rec.front_face;
+            } else {
+                in_b = rec.front_face;
+            }
+            if self.operation.contains(in_a, in_b) == inside {
+                continue;
+            }
+            inside = !inside;
+
+            let mut outward = if rec.front_face {
+                rec.normal
+            } else {
+                -rec.normal
+            };
+            if !from_a && self.operation == CsgOperation::Difference {
+                outward = -outward;
+            }
+            rec.set_face_normal(ray, outward);
+            if !visit(rec) {
+                return;
+            }
+        }
+    }
+}
+
+impl<A: HitTable, B: HitTable> HitTable for Csg<A, B> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let mut found = false;
+        self.crossings(ray, |hit| {
+            if hit.t < t_min {
+                return true;
+            }
+            if hit.t <= t_max {
+                *rec = hit;
+                found = true;
+            }
+            false
+        });
+        found
+    }
+
+    fn hit_all<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, hits: &mut Vec<HitRecord<'a>>) {
+        self.crossings(ray, |hit| {
+            if hit.t > t_max {
+                return false;
+            }
+            if hit.t >= t_min {
+                hits.push(hit);
+            }
+            true
+        });
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let (a_bounded, b_bounded) = (self.a_box.is_some(), self.b_box.is_some());
+        let (a_box, b_box) = (
+            self.a_box.unwrap_or_default(),
+            self.b_box.unwrap_or_default(),
+        );
+        match self.operation {
+            CsgOperation::Union if a_bounded && b_bounded => {
+                *output_box = Aabb::surrounding_box(&a_box, &b_box);
+                true
+            }
+            CsgOperation::Union => false,
+            CsgOperation::Intersection if a_bounded && b_bounded => {
+                // Where the boxes overlap; if they do not, nothing is hit
+                // anyway and either box will do.
+                let min = Vec3::maximum(a_box.min(), b_box.min());
+                let max = Vec3::minimum(a_box.max(), b_box.max());
+                *output_box = if (0..3).all(|axis| min[axis] <= max[axis]) {
+                    Aabb::new(min, max)
+                } else {
+                    a_box
+                };
+                true
+            }
+            CsgOperation::Intersection if b_bounded => {
+                *output_box = b_box;
+                true
+            }
+            CsgOperation::Intersection | CsgOperation::Difference => {
+                *output_box = a_box;
+                a_bounded
+            }
+        }
+    }
+}
+
+/// Whether the line of `ray` passes through `bbox`, which is `None` for
+/// unbounded objects.
+fn may_hit(bbox: Option<Aabb>, ray: &Ray) -> bool {
+    bbox.is_none_or(|bbox| bbox.hit(ray, f64::NEG_INFINITY, f64::INFINITY))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::axis_aligned_box::AxisAlignedBox;
+    use crate::objects::lambertian::Lambertian;
+    use crate::objects::sphere::Sphere;
+    use crate::objects::MaterialType;
+    use crate::vec3::Point3;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn spheres() -> (Sphere, Sphere) {
+        // Unit spheres centred at x = -0.5 and x = 0.5.
+        (
+            Sphere::new(-0.5, 0.0, 0.0, 1.0, MaterialType::None),
+            Sphere::new(
+                0.5,
+                0.0,
+                0.0,
+                1.0,
+                MaterialType::Lambertian(Lambertian::new(0.5, 0.5, 0.5)),
+            ),
+        )
+    }
+
+    /// Where a ray along +x from x = -5 crosses the solid, and whether it is
+    /// going in at each.
+    fn crossings<T: HitTable>(solid: &T) -> Vec<(f64, bool)> {
+        let ray = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        let mut hits = Vec::new();
+        solid.hit_all(&ray, 0.001, f64::INFINITY, &mut hits);
+        hits.iter()
+            .map(|hit| (ray.at(hit.t).x(), hit.front_face))
+            .collect()
+    }
+
+    #[test]
+    fn csg_test() {
+        let (a, b) = spheres();
+        assert_eq!(
+            crossings(&Csg::union(a, b)),
+            vec![(-1.5, true), (1.5, false)]
+        );
+        let (a, b) = spheres();
+        assert_eq!(
+            crossings(&Csg::intersection(a, b)),
+            vec![(-0.5, true), (0.5, false)]
+        );
+        let (a, b) = spheres();
+        let crescent = Csg::difference(a, b);
+        assert_eq!(crossings(&crescent), vec![(-1.5, true), (-0.5, false)]);
+
+        // The far wall of the difference is the near side of the cut-out
+        // sphere, turned to face back out of the hollow, with its material.
+        let ray = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        let mut rec = HitRecord::default();
+        assert!(crescent.hit(&ray, 3.6, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 4.5);
+        assert_eq!(rec.normal, Vec3::new(-1.0, 0.0, 0.0));
+        assert!(!rec.front_face);
+        assert!(matches!(rec.material, MaterialType::Lambertian(_)));
+
+        // Starting inside the solid, the first hit is on the way out.
+        let inside = Ray::new(Point3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(crescent.hit(&inside, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 0.5);
+
+        // Nested: a cube with a sphere cut out of it and another put back.
+        let cube = AxisAlignedBox::new(
+            Point3::new(-1.0, -1.0, -1.0),
+            Point3::new(1.0, 1.0, 1.0),
+            MaterialType::None,
+        );
+        let hollow = Csg::difference(cube, Sphere::new(0.0, 0.0, 0.0, 0.75, MaterialType::None));
+        let nested = Csg::union(hollow, Sphere::new(0.0, 0.0, 0.0, 0.25, MaterialType::None));
+        assert_eq!(
+            crossings(&nested),
+            vec![
+                (-1.0, true),
+                (-0.75, false),
+                (-0.25, true),
+                (0.25, false),
+                (0.75, true),
+                (1.0, false)
+            ]
+        );
+
+        let mut bbox = Aabb::default();
+        let (a, b) = spheres();
+        assert!(Csg::intersection(a, b).bounding_box(&mut bbox));
+        assert_eq!(bbox.min(), Point3::new(-0.5, -1.0, -1.0));
+        assert_eq!(bbox.max(), Point3::new(0.5, 1.0, 1.0));
+    }
+
+    /// Sphere that counts how often its crossings are enumerated.
+    struct Counted {
+        sphere: Sphere,
+        calls: AtomicUsize,
+    }
+
+    impl HitTable for Counted {
+        fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+            self.sphere.hit(ray, t_min, t_max, rec)
+        }
+
+        fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+            self.sphere.bounding_box(output_box)
+        }
+
+        fn hit_all<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, hits: &mut Vec<HitRecord<'a>>) {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            self.sphere.hit_all(ray, t_min, t_max, hits)
+        }
+    }
+
+    #[test]
+    fn skips_missed_operands_test() {
+        let counted = |x| Counted {
+            sphere: Sphere::new(x, 0.0, 0.0, 1.0, MaterialType::None),
+            calls: AtomicUsize::new(0),
+        };
+        let crescent = Csg::difference(counted(-0.5), counted(0.5));
+        let mut rec = HitRecord::default();
+
+        // Passing above `a`, neither operand is looked at.
+        let above = Ray::new(Point3::new(-5.0, 3.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(!crescent.hit(&above, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(crescent.a.calls.load(Ordering::Relaxed), 0);
+        assert_eq!(crescent.b.calls.load(Ordering::Relaxed), 0);
+
+        let through = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(crescent.hit(&through, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 3.5);
+        assert_eq!(crescent.a.calls.load(Ordering::Relaxed), 1);
+        assert_eq!(crescent.b.calls.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::core::aabb::Aabb;
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, HitTable};
+use crate::objects::quadric::{first_root, solve_quadratic, Extent, SurfaceHit};
+use crate::objects::MaterialType;
+use crate::vec3::{Point3, Vec3};
+
+/// Cylinder of `radius` standing on `center` and rising `height` along the
+/// y axis, closed by flat caps unless `uncapped`.
+pub struct Cylinder {
+    center: Point3,
+    radius: f64,
+    extent: Extent,
+    material: MaterialType,
+}
+
+impl Cylinder {
+    pub fn new(center: Point3, radius: f64, height: f64, material: MaterialType) -> Self {
+        Cylinder {
+            center,
+            radius,
+            extent: Extent::new(0.0, height),
+            material,
+        }
+    }
+
+    /// Keeps only the part between heights `y_min` and `y_max` above the
+    /// centre.
+    pub fn with_height_limits(mut self, y_min: f64, y_max: f64) -> Self {
+        self.extent = self.extent.clip(y_min, y_max);
+        self
+    }
+
+    /// Keeps only `degrees` of the turn around the axis, starting from +x.
+    pub fn with_sweep(mut self, degrees: f64) -> Self {
+        self.extent = self.extent.sweep(degrees);
+        self
+    }
+
+    /// Leaves the ends open.
+    pub fn uncapped(mut self) -> Self {
+        self.extent.capped = false;
+        self
+    }
+}
+
+impl HitTable for Cylinder {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let (o, d) = (ray.origin() - self.center, ray.direction());
+        let a = d.x() * d.x() + d.z() * d.z();
+        let b = 2.0 * (o.x() * d.x() + o.z() * d.z());
+        let c = o.x() * o.x() + o.z() * o.z() - self.radius * self.radius;
+        let side = first_root(&solve_quadratic(a, b, c), t_min, t_max, |t| {
+            let p = o + t * d;
+            let phi = self.extent.contains(p)?;
+            let (u, v) = self.extent.side_uv(phi, p.y());
+            Some(SurfaceHit {
+                t,
+                p,
+                normal: Vec3::new(p.x(), 0.0, p.z()),
+                u,
+                v,
+            })
+        });
+
+        let caps = self.extent.hit_caps(o, d, |_| self.radius, t_min, t_max);
+
+        match SurfaceHit::nearest(side, caps) {
+            Some(hit) => {
+                hit.record(ray, self.center, &self.material, rec);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let r = self.radius;
+        *output_box = Aabb::new(
+            self.center + Vec3::new(-r, self.extent.y_min, -r),
+            self.center + Vec3::new(r, self.extent.y_max, r),
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cylinder_test() {
+        let cylinder = Cylinder::new(Point3::new(0.0, 1.0, 0.0), 1.0, 2.0, MaterialType::None);
+        let mut rec = HitRecord::default();
+
+        let side = Ray::new(Point3::new(-5.0, 2.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(cylinder.hit(&side, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 4.0);
+        assert_eq!(rec.normal, Vec3::new(-1.0, 0.0, 0.0));
+        assert_eq!((rec.u, rec.v), (0.5, 0.5));
+
+        let down = Ray::new(Point3::new(0.5, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(cylinder.hit(&down, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 2.0);
+        assert_eq!(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+
+        // Open and cut in half, the ray falls inside and meets the far side
+        // from within.
+        let open = Cylinder::new(Point3::new(0.0, 1.0, 0.0), 1.0, 2.0, MaterialType::None)
+            .uncapped()
+            .with_sweep(180.0);
+        assert!(!open.hit(&down, 0.001, f64::INFINITY, &mut rec));
+        let slanted = Ray::new(Point3::new(0.0, 2.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(open.hit(&slanted, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 6.0);
+        assert!(!rec.front_face);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::core::ray::Ray;
-use crate::core::{random_f64, HitRecord, Material};
+use crate::core::{HitRecord, Material};
+use crate::samplers::Sampler;
 use crate::vec3::{Color, Vec3};
 
 #[derive(Clone, Copy)]
@@ -14,6 +15,7 @@ impl Material for Dielectric {
         rec: &HitRecord,
         attenuation: &mut Color,
         scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
     ) -> bool {
         *attenuation = Color::new(1.0, 1.0, 1.0);
         let refraction_ratio = if rec.front_face {
@@ -27,16 +29,16 @@ impl Material for Dielectric {
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
+        let u = sampler.get_1d();
 
-        let direction = if cannot_refract
-            || Dielectric::reflectance(cos_theta, refraction_ratio) > random_f64()
-        {
-            Vec3::reflect(unit_direction, rec.normal)
-        } else {
-            Vec3::refract(unit_direction, rec.normal, refraction_ratio)
-        };
+        let direction =
+            if cannot_refract || Dielectric::reflectance(cos_theta, refraction_ratio) > u {
+                Vec3::reflect(unit_direction, rec.normal)
+            } else {
+                Vec3::refract(unit_direction, rec.normal, refraction_ratio)
+            };
 
-        *scattered = Ray::new(rec.p, direction);
+        *scattered = Ray::with_time(rec.p, direction, r_in.time());
 
         true
     }
@@ -0,0 +1,55 @@
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, Material};
+use crate::samplers::Sampler;
+use crate::vec3::Color;
+
+#[derive(Clone, Copy)]
+pub struct DiffuseLight {
+    emit: Color,
+}
+
+impl DiffuseLight {
+    pub fn new(r: f64, g: f64, b: f64) -> DiffuseLight {
+        DiffuseLight {
+            emit: Color::new(r, g, b),
+        }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _r_in: &Ray,
+        _rec: &HitRecord,
+        _attenuation: &mut Color,
+        _scattered: &mut Ray,
+        _sampler: &mut dyn Sampler,
+    ) -> bool {
+        false
+    }
+
+    fn emitted(&self, _rec: &HitRecord) -> Color {
+        self.emit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::MaterialType;
+    use crate::samplers::{SamplerKind, SamplerType};
+    use crate::vec3::{Point3, Vec3};
+
+    #[test]
+    fn emits_without_scattering_test() {
+        let light = MaterialType::DiffuseLight(DiffuseLight::new(4.0, 2.0, 1.0));
+        let r_in = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let rec = HitRecord::default();
+        let mut attenuation = Color::new(0.0, 0.0, 0.0);
+        let mut scattered = Ray::default();
+        let mut sampler = SamplerType::new(SamplerKind::Independent, 1, 0);
+
+        assert_eq!(light.emitted(&rec), Color::new(4.0, 2.0, 1.0));
+        assert!(!light.scatter(&r_in, &rec, &mut attenuation, &mut scattered, &mut sampler));
+    }
+}
@@ -0,0 +1,167 @@
+use std::f64::consts::PI;
+
+use crate::core::aabb::Aabb;
+use crate::core::onb::Onb;
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, HitTable};
+use crate::objects::plane::area_light_pdf;
+use crate::objects::MaterialType;
+use crate::samplers::Sampler;
+use crate::vec3::{Point3, Vec3};
+
+const BOX_PADDING: f64 = 1e-4;
+
+/// Flat disk of `radius` around `center`, facing `normal`, with an optional
+/// hole of `inner_radius` making it an annulus. `u` goes once around the
+/// centre and `v` runs from the inner edge to the outer one.
+pub struct Disk {
+    center: Point3,
+    frame: Onb,
+    radius: f64,
+    inner_radius: f64,
+    material: MaterialType,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f64, material: MaterialType) -> Self {
+        Disk::annulus(center, normal, 0.0, radius, material)
+    }
+
+    /// # Panics
+    ///
+    /// Panics unless `0 <= inner_radius < radius`.
+    pub fn annulus(
+        center: Point3,
+        normal: Vec3,
+        inner_radius: f64,
+        radius: f64,
+        material: MaterialType,
+    ) -> Self {
+        assert!(
+            inner_radius >= 0.0 && inner_radius < radius,
+            "an annulus needs 0 <= inner radius < radius"
+        );
+        Disk {
+            center,
+            frame: Onb::build_from_w(normal),
+            radius,
+            inner_radius,
+            material,
+        }
+    }
+
+    fn area(&self) -> f64 {
+        PI * (self.radius * self.radius - self.inner_radius * self.inner_radius)
+    }
+}
+
+impl HitTable for Disk {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let normal = self.frame.w();
+        let denom = Vec3::dot(normal, ray.direction());
+        if denom.abs() < 1e-8 {
+            return false;
+        }
+        let t = Vec3::dot(normal, self.center - ray.origin()) / denom;
+        if t < t_min || t_max < t {
+            return false;
+        }
+
+        let p = ray.at(t);
+        let x = Vec3::dot(p - self.center, self.frame.u());
+        let y = Vec3::dot(p - self.center, self.frame.v());
+        let r = (x * x + y * y).sqrt();
+        if r > self.radius || r < self.inner_radius {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        let phi = y.atan2(x);
+        rec.u = if phi < 0.0 { phi + 2.0 * PI } else { phi } / (2.0 * PI);
+        rec.v = (r - self.inner_radius) / (self.radius - self.inner_radius);
+        rec.material = &self.material;
+        rec.set_face_normal(ray, normal);
+
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        // A circle of radius r reaches r * sqrt(1 - n_a^2) along axis a.
+        let n = self.frame.w();
+        let extent = Vec3::new(
+            self.radius * (1.0 - n.x() * n.x()).max(0.0).sqrt(),
+            self.radius * (1.0 - n.y() * n.y()).max(0.0).sqrt(),
+            self.radius * (1.0 - n.z() * n.z()).max(0.0).sqrt(),
+        );
+        *output_box = Aabb::new(self.center - extent, self.center + extent).pad(BOX_PADDING);
+        true
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        area_light_pdf(self, self.area(), origin, direction)
+    }
+
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        let (s, t) = sampler.get_2d();
+        let (inner2, outer2) = (
+            self.inner_radius * self.inner_radius,
+            self.radius * self.radius,
+        );
+        let r = (inner2 + s * (outer2 - inner2)).sqrt();
+        let phi = 2.0 * PI * t;
+        let p = self.center
+            + self
+                .frame
+                .local(Vec3::new(r * phi.cos(), r * phi.sin(), 0.0));
+        p - origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::samplers::{SamplerKind, SamplerType};
+
+    #[test]
+    fn annulus_test() {
+        let annulus = Disk::annulus(
+            Point3::new(0.0, 2.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            0.5,
+            1.0,
+            MaterialType::None,
+        );
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        let mut rec = HitRecord::default();
+        let through = |x: f64| Ray::new(Point3::new(x, 0.0, 0.0), up);
+        assert!(!annulus.hit(&through(0.0), 0.001, f64::INFINITY, &mut rec));
+        assert!(!annulus.hit(&through(1.1), 0.001, f64::INFINITY, &mut rec));
+        assert!(annulus.hit(&through(0.75), 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 2.0);
+        assert!((rec.v - 0.5).abs() < 1e-12);
+        assert!(rec.front_face);
+
+        let mut bbox = Aabb::default();
+        assert!(annulus.bounding_box(&mut bbox));
+        assert!((bbox.max().x() - 1.0).abs() < 1e-12);
+        assert!((bbox.max().y() - 2.0).abs() < 1e-3);
+
+        // Sampled directions land on the annulus, and averaging 1 / pdf over
+        // them estimates the solid angle it covers from the origin.
+        let mut sampler = SamplerType::new(SamplerKind::Sobol, 1024, 0);
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        let mut solid_angle = 0.0;
+        for i in 0..1024 {
+            sampler.start_pixel_sample(0, 0, i);
+            let direction = annulus.random(origin, &mut sampler);
+            let pdf = annulus.pdf_value(origin, direction);
+            assert!(pdf > 0.0);
+            solid_angle += 1.0 / pdf / 1024.0;
+        }
+        // Solid angle of a disk of radius r at height h is
+        // 2 pi (1 - h / sqrt(h^2 + r^2)).
+        let cap = |r: f64| 2.0 * PI * (1.0 - 2.0 / (4.0 + r * r).sqrt());
+        assert!((solid_angle - (cap(1.0) - cap(0.5))).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,312 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::core::aabb::Aabb;
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, HitTable};
+use crate::objects::constant_medium::ray_rng;
+use crate::objects::MaterialType;
+use crate::textures::perlin::Perlin;
+use crate::vec3::{Point3, Vec3};
+
+/// Densities on a regular grid of voxels spanning the unit cube, with x
+/// varying fastest, then y, then z.
+pub struct DensityGrid {
+    resolution: [usize; 3],
+    values: Vec<f64>,
+    max: f64,
+}
+
+impl DensityGrid {
+    /// # Panics
+    ///
+    /// Panics if `values` does not hold one non-negative value per voxel.
+    pub fn new(resolution: [usize; 3], values: Vec<f64>) -> DensityGrid {
+        assert_eq!(
+            values.len(),
+            resolution.iter().product::<usize>(),
+            "a density grid needs one value per voxel"
+        );
+        assert!(
+            values.iter().all(|&v| v >= 0.0),
+            "densities must not be negative"
+        );
+        let max = values.iter().copied().fold(0.0, f64::max);
+        DensityGrid {
+            resolution,
+            values,
+            max,
+        }
+    }
+
+    /// Reads a raw file of little-endian 32-bit floats, one per voxel in grid
+    /// order and nothing else.
+    pub fn load<P: AsRef<Path>>(path: P, resolution: [usize; 3]) -> io::Result<DensityGrid> {
+        let bytes = fs::read(path)?;
+        let count = resolution.iter().product::<usize>();
+        if bytes.len() != count * 4 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "expected {} bytes for a {}x{}x{} grid, found {}",
+                    count * 4,
+                    resolution[0],
+                    resolution[1],
+                    resolution[2],
+                    bytes.len()
+                ),
+            ));
+        }
+        let values: Vec<f64> = bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64)
+            .collect();
+        if values.iter().any(|v| v.is_nan() || *v < 0.0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "densities must not be negative",
+            ));
+        }
+        Ok(DensityGrid::new(resolution, values))
+    }
+
+    /// A cloud-like puff of turbulence `scale` times finer than the grid,
+    /// fading out towards a ball touching the sides of the cube.
+    pub fn from_noise(resolution: [usize; 3], scale: f64, seed: u64) -> DensityGrid {
+        let perlin = Perlin::new(seed);
+        let centre = Point3::new(0.5, 0.5, 0.5);
+        let mut values = Vec::with_capacity(resolution.iter().product());
+        for k in 0..resolution[2] {
+            for j in 0..resolution[1] {
+                for i in 0..resolution[0] {
+                    let p = Point3::new(
+                        (i as f64 + 0.5) / resolution[0] as f64,
+                        (j as f64 + 0.5) / resolution[1] as f64,
+                        (k as f64 + 0.5) / resolution[2] as f64,
+                    );
+                    let falloff = (1.0 - 2.0 * (p - centre).length()).max(0.0);
+                    values.push(falloff * perlin.turb(&(scale * p), 7));
+                }
+            }
+        }
+        DensityGrid::new(resolution, values)
+    }
+
+    pub fn resolution(&self) -> [usize; 3] {
+        self.resolution
+    }
+
+    /// Largest density anywhere in the grid.
+    pub fn max(&self) -> f64 {
+        self.max
+    }
+
+    /// Density at `p` in the unit cube, interpolated trilinearly between the
+    /// voxel centres and held constant beyond the outermost ones.
+    pub fn lookup(&self, p: Point3) -> f64 {
+        let mut index = [[0; 2]; 3];
+        let mut weight = [0.0; 3];
+        for axis in 0..3 {
+            let n = self.resolution[axis];
+            let x = (p[axis] * n as f64 - 0.5).clamp(0.0, (n - 1) as f64);
+            let i = x.floor() as usize;
+            index[axis] = [i, (i + 1).min(n - 1)];
+            weight[axis] = x - i as f64;
+        }
+
+        let mut density = 0.0;
+        for (dk, &k) in index[2].iter().enumerate() {
+            for (dj, &j) in index[1].iter().enumerate() {
+                for (di, &i) in index[0].iter().enumerate() {
+                    let w = [di, dj, dk]
+                        .iter()
+                        .zip(weight)
+                        .map(|(&d, w)| if d == 0 { 1.0 - w } else { w })
+                        .product::<f64>();
+                    density += w * self.voxel(i, j, k);
+                }
+            }
+        }
+        density
+    }
+
+    fn voxel(&self, i: usize, j: usize, k: usize) -> f64 {
+        self.values[(k * self.resolution[1] + j) * self.resolution[0] + i]
+    }
+}
+
+/// Smoke or cloud whose density varies through a box, given by a
+/// `DensityGrid` stretched over the box and scaled by `density`.
+///
+/// Scattering distances are sampled by delta tracking: the ray takes steps
+/// as though the whole box were as dense as its densest voxel, and at each
+/// step scatters with the ratio of the actual density to that maximum, or
+/// carries on. Shadow rays estimate transmittance by ratio tracking, taking
+/// the same steps but multiplying by the chance of carrying on at each
+/// instead of choosing.
+pub struct GridMedium {
+    bounds: Aabb,
+    grid: DensityGrid,
+    density: f64,
+    phase_function: MaterialType,
+}
+
+impl GridMedium {
+    pub fn new(
+        bounds: Aabb,
+        grid: DensityGrid,
+        density: f64,
+        phase_function: MaterialType,
+    ) -> GridMedium {
+        GridMedium {
+            bounds,
+            grid,
+            density,
+            phase_function,
+        }
+    }
+
+    pub fn density(&self, p: Point3) -> f64 {
+        let min = self.bounds.min();
+        let extent = self.bounds.max() - min;
+        let local = p - min;
+        self.density
+            * self.grid.lookup(Point3::new(
+                local.x() / extent.x(),
+                local.y() / extent.y(),
+                local.z() / extent.z(),
+            ))
+    }
+
+    fn majorant(&self) -> f64 {
+        self.density * self.grid.max()
+    }
+
+    /// Range of `t` within [`t_min`, `t_max`] where `ray` is inside the box,
+    /// along with where it first enters the box.
+    fn interval(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64, f64)> {
+        let (entry, exit) = self
+            .bounds
+            .hit_interval(ray, f64::NEG_INFINITY, f64::INFINITY)?;
+        let t_enter = entry.max(t_min).max(0.0);
+        let t_exit = exit.min(t_max);
+        if t_enter >= t_exit {
+            return None;
+        }
+        Some((t_enter, t_exit, entry))
+    }
+}
+
+impl HitTable for GridMedium {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let majorant = self.majorant();
+        let (mut t, t_exit, entry) = match self.interval(ray, t_min, t_max) {
+            Some(interval) if majorant > 0.0 => interval,
+            _ => return false,
+        };
+
+        let mut rng = ray_rng(ray, entry);
+        let step = 1.0 / (majorant * ray.direction().length());
+        loop {
+            t -= (1.0 - rng.f64()).ln() * step;
+            if t >= t_exit {
+                return false;
+            }
+            let p = ray.at(t);
+            if rng.f64() * majorant < self.density(p) {
+                rec.t = t;
+                rec.p = p;
+                // Arbitrary, a medium has no surface.
+                rec.normal = Vec3::new(1.0, 0.0, 0.0);
+                rec.front_face = true;
+                rec.material = &self.phase_function;
+                return true;
+            }
+        }
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = self.bounds;
+        true
+    }
+
+    fn hit_surface<'a>(
+        &'a self,
+        _ray: &Ray,
+        _t_min: f64,
+        _t_max: f64,
+        _rec: &mut HitRecord<'a>,
+    ) -> bool {
+        false
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let majorant = self.majorant();
+        let (mut t, t_exit, entry) = match self.interval(ray, t_min, t_max) {
+            Some(interval) if majorant > 0.0 => interval,
+            _ => return 1.0,
+        };
+
+        let mut rng = ray_rng(ray, entry);
+        let step = 1.0 / (majorant * ray.direction().length());
+        let mut transmittance = 1.0;
+        loop {
+            t -= (1.0 - rng.f64()).ln() * step;
+            if t >= t_exit {
+                return transmittance;
+            }
+            transmittance *= 1.0 - self.density(ray.at(t)) / majorant;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::isotropic::Isotropic;
+    use crate::vec3::Color;
+
+    #[test]
+    fn lookup_test() {
+        let grid = DensityGrid::new([2, 1, 1], vec![1.0, 3.0]);
+        assert_eq!(grid.max(), 3.0);
+        assert_eq!(grid.lookup(Point3::new(0.25, 0.5, 0.5)), 1.0);
+        assert_eq!(grid.lookup(Point3::new(0.5, 0.5, 0.5)), 2.0);
+        assert_eq!(grid.lookup(Point3::new(1.0, 0.0, 1.0)), 3.0);
+    }
+
+    #[test]
+    fn tracking_test() {
+        // Density along z is 0 for the first quarter of the cube, 2 for the
+        // last and linear in between, for an optical depth of 1 straight
+        // through.
+        let grid = DensityGrid::new([1, 1, 2], vec![0.0, 2.0]);
+        let bounds = Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0));
+        let medium = GridMedium::new(
+            bounds,
+            grid,
+            1.0,
+            MaterialType::Isotropic(Isotropic::new(Color::new(1.0, 1.0, 1.0))),
+        );
+        let expected = (-1.0f64).exp();
+
+        let mut passed = 0;
+        let mut transmittance = 0.0;
+        for i in 0..10000 {
+            let origin = Point3::new(0.5 + i as f64 * 1e-9, 0.5, -5.0);
+            let ray = Ray::new(origin, Vec3::new(0.0, 0.0, 2.0));
+            let mut rec = HitRecord::default();
+            if !medium.hit(&ray, 0.001, f64::INFINITY, &mut rec) {
+                passed += 1;
+            }
+            transmittance += medium.transmittance(&ray, 0.001, f64::INFINITY);
+        }
+        assert!(
+            (passed as f64 / 10000.0 - expected).abs() < 0.02,
+            "{}",
+            passed
+        );
+        assert!((transmittance / 10000.0 - expected).abs() < 0.01);
+    }
+}
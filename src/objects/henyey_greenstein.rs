@@ -0,0 +1,119 @@
+use std::f64::consts::PI;
+
+use crate::core::onb::Onb;
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, Material, Texture};
+use crate::objects::isotropic::Isotropic;
+use crate::objects::MaterialType;
+use crate::samplers::Sampler;
+use crate::textures::TextureType;
+use crate::vec3::{Color, Vec3};
+
+/// Henyey-Greenstein phase function, which scatters light mostly forwards
+/// when the asymmetry `g` is positive, as in clouds and haze, and mostly
+/// backwards when it is negative. Zero is isotropic.
+#[derive(Clone)]
+pub struct HenyeyGreenstein {
+    albedo: TextureType,
+    g: f64,
+}
+
+impl HenyeyGreenstein {
+    /// # Panics
+    ///
+    /// Panics unless `g` is strictly between -1 and 1.
+    pub fn new(albedo: Color, g: f64) -> HenyeyGreenstein {
+        HenyeyGreenstein::with_texture(TextureType::from(albedo), g)
+    }
+
+    pub fn with_texture(albedo: TextureType, g: f64) -> HenyeyGreenstein {
+        assert!(g > -1.0 && g < 1.0, "asymmetry must be between -1 and 1");
+        HenyeyGreenstein { albedo, g }
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        let (u1, u2) = sampler.get_2d();
+        let g = self.g;
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * u1
+        } else {
+            let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u1);
+            ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * u2;
+        let uvw = Onb::build_from_w(r_in.direction());
+        let direction = uvw.local(Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ));
+
+        *scattered = Ray::with_time(rec.p, direction, r_in.time());
+        *attenuation = self.albedo.value(rec.u, rec.v, &rec.p);
+
+        true
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, _rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cos_theta = Vec3::dot(
+            r_in.direction().unit_vector(),
+            scattered.direction().unit_vector(),
+        );
+        let denom = 1.0 + self.g * self.g - 2.0 * self.g * cos_theta;
+        (1.0 - self.g * self.g) / (4.0 * PI * denom * denom.sqrt())
+    }
+}
+
+/// Phase function of a medium scattering a fraction `albedo` of the light
+/// with asymmetry `g`: isotropic when `g` is zero, Henyey-Greenstein
+/// otherwise.
+pub fn phase_function(albedo: TextureType, g: f64) -> MaterialType {
+    if g == 0.0 {
+        MaterialType::Isotropic(Isotropic::with_texture(albedo))
+    } else {
+        MaterialType::HenyeyGreenstein(HenyeyGreenstein::with_texture(albedo, g))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::samplers::{SamplerKind, SamplerType};
+    use crate::vec3::Point3;
+
+    #[test]
+    fn henyey_greenstein_test() {
+        let phase = HenyeyGreenstein::new(Color::new(1.0, 1.0, 1.0), 0.6);
+        let r_in = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 2.0, 2.0));
+        let rec = HitRecord::default();
+        let mut sampler = SamplerType::new(SamplerKind::Sobol, 4096, 0);
+
+        // The mean cosine of the scattering angle is the asymmetry.
+        let mut cos_sum = 0.0;
+        let mut inverse_pdf_sum = 0.0;
+        for i in 0..4096 {
+            sampler.start_pixel_sample(0, 0, i);
+            let mut attenuation = Color::default();
+            let mut scattered = Ray::default();
+            assert!(phase.scatter(&r_in, &rec, &mut attenuation, &mut scattered, &mut sampler));
+            cos_sum += Vec3::dot(
+                r_in.direction().unit_vector(),
+                scattered.direction().unit_vector(),
+            );
+            inverse_pdf_sum += 1.0 / phase.scattering_pdf(&r_in, &rec, &scattered);
+        }
+        assert!((cos_sum / 4096.0 - 0.6).abs() < 0.01);
+        // Averaging 1 / pdf estimates the solid angle of the whole sphere.
+        assert!((inverse_pdf_sum / 4096.0 / (4.0 * PI) - 1.0).abs() < 0.05);
+    }
+}
@@ -1,5 +1,8 @@
+use crate::core::aabb::Aabb;
 use crate::core::ray::Ray;
 use crate::core::{HitRecord, HitTable};
+use crate::samplers::Sampler;
+use crate::vec3::{Point3, Vec3};
 
 pub struct HitTableList<T>
 where
@@ -23,13 +26,34 @@ where
     pub fn add(&mut self, object: T) {
         self.objects.push(object);
     }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    pub fn into_objects(self) -> Vec<T> {
+        self.objects
+    }
+}
+
+impl<T> Default for HitTableList<T>
+where
+    T: HitTable,
+{
+    fn default() -> Self {
+        HitTableList::new()
+    }
 }
 
 impl<T> HitTable for HitTableList<T>
 where
     T: HitTable,
 {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
         let mut hit_anything = false;
         let mut closest_so_far = t_max;
         let mut hit_rec = Default::default();
@@ -44,4 +68,69 @@ where
 
         hit_anything
     }
+
+    fn hit_surface<'a>(
+        &'a self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord<'a>,
+    ) -> bool {
+        let mut hit_anything = false;
+        let mut closest_so_far = t_max;
+        let mut hit_rec = Default::default();
+
+        for object in self.objects.iter() {
+            if object.hit_surface(ray, t_min, closest_so_far, &mut hit_rec) {
+                hit_anything = true;
+                closest_so_far = hit_rec.t;
+                *rec = hit_rec;
+            }
+        }
+
+        hit_anything
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        self.objects
+            .iter()
+            .map(|object| object.transmittance(ray, t_min, t_max))
+            .product()
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let mut first_box = true;
+        let mut temp_box = Aabb::default();
+
+        for object in self.objects.iter() {
+            if !object.bounding_box(&mut temp_box) {
+                return false;
+            }
+            *output_box = if first_box {
+                temp_box
+            } else {
+                Aabb::surrounding_box(output_box, &temp_box)
+            };
+            first_box = false;
+        }
+
+        !first_box
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+        let weight = 1.0 / self.objects.len() as f64;
+        self.objects
+            .iter()
+            .map(|object| weight * object.pdf_value(origin, direction))
+            .sum()
+    }
+
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        let len = self.objects.len();
+        let index = ((sampler.get_1d() * len as f64) as usize).min(len - 1);
+        self.objects[index].random(origin, sampler)
+    }
 }
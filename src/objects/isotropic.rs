@@ -0,0 +1,42 @@
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, Material, Texture};
+use crate::samplers::{uniform_sphere, Sampler};
+use crate::textures::TextureType;
+use crate::vec3::Color;
+
+/// Phase function of a participating medium that scatters light equally in
+/// every direction.
+#[derive(Clone)]
+pub struct Isotropic {
+    albedo: TextureType,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Isotropic {
+        Isotropic::with_texture(TextureType::from(albedo))
+    }
+
+    pub fn with_texture(albedo: TextureType) -> Isotropic {
+        Isotropic { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        *scattered = Ray::with_time(rec.p, uniform_sphere(sampler.get_2d()), r_in.time());
+        *attenuation = self.albedo.value(rec.u, rec.v, &rec.p);
+
+        true
+    }
+
+    fn scattering_pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        1.0 / (4.0 * std::f64::consts::PI)
+    }
+}
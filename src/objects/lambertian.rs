@@ -1,17 +1,21 @@
 use crate::core::ray::Ray;
-use crate::core::{HitRecord, Material};
+use crate::core::{HitRecord, Material, Texture};
+use crate::samplers::{uniform_sphere, Sampler};
+use crate::textures::TextureType;
 use crate::vec3::{Color, Vec3};
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Lambertian {
-    albedo: Color,
+    albedo: TextureType,
 }
 
 impl Lambertian {
     pub fn new(r: f64, g: f64, b: f64) -> Lambertian {
-        Lambertian {
-            albedo: Color::new(r, g, b),
-        }
+        Lambertian::with_texture(TextureType::from(Color::new(r, g, b)))
+    }
+
+    pub fn with_texture(albedo: TextureType) -> Lambertian {
+        Lambertian { albedo }
     }
 }
 
@@ -22,14 +26,24 @@ impl Material for Lambertian {
         rec: &HitRecord,
         attenuation: &mut Color,
         scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
     ) -> bool {
-        let mut scatter_direction = rec.normal + Vec3::random_unit_vector();
+        let mut scatter_direction = rec.normal + uniform_sphere(sampler.get_2d());
         if scatter_direction.near_zero() {
             scatter_direction = rec.normal;
         }
-        *scattered = Ray::new(rec.p, scatter_direction);
-        *attenuation = self.albedo;
+        *scattered = Ray::with_time(rec.p, scatter_direction, r_in.time());
+        *attenuation = self.albedo.value(rec.u, rec.v, &rec.p);
 
         true
     }
+
+    fn scattering_pdf(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cosine = Vec3::dot(rec.normal, scattered.direction().unit_vector());
+        if cosine < 0.0 {
+            0.0
+        } else {
+            cosine / std::f64::consts::PI
+        }
+    }
 }
@@ -1,20 +1,23 @@
 use crate::core::ray::Ray;
-use crate::core::{HitRecord, Material};
+use crate::core::{HitRecord, Material, Texture};
+use crate::samplers::{uniform_ball, Sampler};
+use crate::textures::TextureType;
 use crate::vec3::{Color, Vec3};
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Metal {
-    albedo: Color,
+    albedo: TextureType,
     fuzz: f64,
 }
 
 impl Metal {
     pub fn new(r: f64, g: f64, b: f64, f: f64) -> Metal {
+        Metal::with_texture(TextureType::from(Color::new(r, g, b)), f)
+    }
+
+    pub fn with_texture(albedo: TextureType, f: f64) -> Metal {
         let fuzz = if f < 1.0 { f } else { 1.0 };
-        Metal {
-            albedo: Color::new(r, g, b),
-            fuzz,
-        }
+        Metal { albedo, fuzz }
     }
 }
 
@@ -25,10 +28,15 @@ impl Material for Metal {
         rec: &HitRecord,
         attenuation: &mut Color,
         scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
     ) -> bool {
         let reflected = Vec3::reflect(r_in.direction().unit_vector(), rec.normal);
-        *scattered = Ray::new(rec.p, reflected + self.fuzz * Vec3::random_in_unit_sphere());
-        *attenuation = self.albedo;
+        *scattered = Ray::with_time(
+            rec.p,
+            reflected + self.fuzz * uniform_ball(sampler.get_2d(), sampler.get_1d()),
+            r_in.time(),
+        );
+        *attenuation = self.albedo.value(rec.u, rec.v, &rec.p);
         Vec3::dot(scattered.direction(), rec.normal) > 0.0
     }
 }
@@ -1,19 +1,82 @@
+pub mod axis_aligned_box;
+pub mod bvh;
+pub mod cone;
+pub mod constant_medium;
+pub mod csg;
+pub mod cylinder;
 pub mod dielectric;
+pub mod diffuse_light;
+pub mod disk;
+pub mod grid_medium;
+pub mod henyey_greenstein;
 pub mod hittable_list;
+pub mod isotropic;
 pub mod lambertian;
 pub mod metal;
+pub mod moving_sphere;
+pub mod paraboloid;
+pub mod plane;
+pub mod polygon;
+pub mod quad;
+pub mod quadric;
 pub mod sphere;
+pub mod torus;
+pub mod transformed;
+pub mod triangle;
+pub mod triangle_mesh;
 
-#[derive(Clone, Copy)]
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, Material};
+use crate::samplers::Sampler;
+use crate::vec3::Color;
+
+#[derive(Clone, Default)]
 pub enum MaterialType {
     Lambertian(lambertian::Lambertian),
     Metal(metal::Metal),
     Dielectric(dielectric::Dielectric),
+    DiffuseLight(diffuse_light::DiffuseLight),
+    Isotropic(isotropic::Isotropic),
+    HenyeyGreenstein(henyey_greenstein::HenyeyGreenstein),
+    #[default]
     None,
 }
 
-impl Default for MaterialType {
-    fn default() -> Self {
-        MaterialType::None
+impl Material for MaterialType {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        match self {
+            MaterialType::Lambertian(l) => l.scatter(r_in, rec, attenuation, scattered, sampler),
+            MaterialType::Metal(m) => m.scatter(r_in, rec, attenuation, scattered, sampler),
+            MaterialType::Dielectric(d) => d.scatter(r_in, rec, attenuation, scattered, sampler),
+            MaterialType::DiffuseLight(d) => d.scatter(r_in, rec, attenuation, scattered, sampler),
+            MaterialType::Isotropic(i) => i.scatter(r_in, rec, attenuation, scattered, sampler),
+            MaterialType::HenyeyGreenstein(h) => {
+                h.scatter(r_in, rec, attenuation, scattered, sampler)
+            }
+            MaterialType::None => false,
+        }
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        match self {
+            MaterialType::DiffuseLight(d) => d.emitted(rec),
+            _ => Color::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        match self {
+            MaterialType::Lambertian(l) => l.scattering_pdf(r_in, rec, scattered),
+            MaterialType::Isotropic(i) => i.scattering_pdf(r_in, rec, scattered),
+            MaterialType::HenyeyGreenstein(h) => h.scattering_pdf(r_in, rec, scattered),
+            _ => 0.0,
+        }
     }
 }
@@ -0,0 +1,128 @@
+use crate::core::aabb::Aabb;
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, HitTable};
+use crate::objects::sphere::hit_sphere;
+use crate::objects::MaterialType;
+use crate::vec3::{Point3, Vec3};
+
+/// A sphere whose centre moves through a list of keyframes, interpolated
+/// linearly between them and held still before the first and after the
+/// last. Rays see it where it is at their own time, so a shutter that stays
+/// open while it moves blurs it along its path.
+///
+/// Moving spheres are not sampled as lights, since light sampling does not
+/// know the time of the ray it is choosing a direction for.
+pub struct MovingSphere {
+    keyframes: Vec<(f64, Point3)>,
+    radius: f64,
+    material: MaterialType,
+}
+
+impl MovingSphere {
+    /// A sphere moving at constant speed from `center0` at `time0` to
+    /// `center1` at `time1`.
+    pub fn new(
+        center0: Point3,
+        time0: f64,
+        center1: Point3,
+        time1: f64,
+        radius: f64,
+        material: MaterialType,
+    ) -> Self {
+        MovingSphere::keyframed(vec![(time0, center0), (time1, center1)], radius, material)
+    }
+
+    /// A sphere passing through the `(time, center)` pairs of `keyframes`,
+    /// which need not be sorted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `keyframes` is empty.
+    pub fn keyframed(
+        mut keyframes: Vec<(f64, Point3)>,
+        radius: f64,
+        material: MaterialType,
+    ) -> Self {
+        assert!(!keyframes.is_empty(), "a moving sphere needs a keyframe");
+        keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        MovingSphere {
+            keyframes,
+            radius,
+            material,
+        }
+    }
+
+    pub fn center(&self, time: f64) -> Point3 {
+        let next = self.keyframes.partition_point(|&(t, _)| t <= time);
+        if next == 0 {
+            return self.keyframes[0].1;
+        }
+        if next == self.keyframes.len() {
+            return self.keyframes[next - 1].1;
+        }
+        let (t0, c0) = self.keyframes[next - 1];
+        let (t1, c1) = self.keyframes[next];
+        c0 + ((time - t0) / (t1 - t0)) * (c1 - c0)
+    }
+}
+
+impl HitTable for MovingSphere {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        hit_sphere(
+            self.center(ray.time()),
+            self.radius,
+            &self.material,
+            ray,
+            t_min,
+            t_max,
+            rec,
+        )
+    }
+
+    /// The path between keyframes is straight, so the boxes around the
+    /// keyframes enclose the sphere at every time.
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        *output_box = self
+            .keyframes
+            .iter()
+            .map(|&(_, c)| Aabb::new(c - r, c + r))
+            .reduce(|a, b| Aabb::surrounding_box(&a, &b))
+            .unwrap();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moving_sphere_test() {
+        let sphere = MovingSphere::keyframed(
+            vec![
+                (1.0, Point3::new(2.0, 0.0, 0.0)),
+                (0.0, Point3::new(0.0, 0.0, 0.0)),
+                (2.0, Point3::new(2.0, 2.0, 0.0)),
+            ],
+            0.5,
+            MaterialType::None,
+        );
+        assert_eq!(sphere.center(0.5), Point3::new(1.0, 0.0, 0.0));
+        assert_eq!(sphere.center(1.5), Point3::new(2.0, 1.0, 0.0));
+        assert_eq!(sphere.center(-1.0), Point3::new(0.0, 0.0, 0.0));
+        assert_eq!(sphere.center(3.0), Point3::new(2.0, 2.0, 0.0));
+
+        let mut rec = HitRecord::default();
+        let down = Vec3::new(0.0, 0.0, -1.0);
+        let early = Ray::with_time(Point3::new(0.0, 0.0, 5.0), down, 0.0);
+        let late = Ray::with_time(Point3::new(0.0, 0.0, 5.0), down, 1.0);
+        assert!(sphere.hit(&early, 0.0, f64::INFINITY, &mut rec));
+        assert!(!sphere.hit(&late, 0.0, f64::INFINITY, &mut rec));
+
+        let mut bbox = Aabb::default();
+        assert!(sphere.bounding_box(&mut bbox));
+        assert_eq!(bbox.min(), Point3::new(-0.5, -0.5, -0.5));
+        assert_eq!(bbox.max(), Point3::new(2.5, 2.5, 0.5));
+    }
+}
@@ -0,0 +1,130 @@
+use crate::core::aabb::Aabb;
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, HitTable};
+use crate::objects::quadric::{first_root, solve_quadratic, Extent, SurfaceHit};
+use crate::objects::MaterialType;
+use crate::vec3::{Point3, Vec3};
+
+/// Bowl with its lowest point on `center`, opening upwards along the y axis
+/// to `radius` at `height`. The rim is closed by a flat cap unless
+/// `uncapped`, as is the bottom when the height limits cut it off.
+pub struct Paraboloid {
+    center: Point3,
+    radius: f64,
+    height: f64,
+    extent: Extent,
+    material: MaterialType,
+}
+
+impl Paraboloid {
+    pub fn new(center: Point3, radius: f64, height: f64, material: MaterialType) -> Self {
+        Paraboloid {
+            center,
+            radius,
+            height,
+            extent: Extent::new(0.0, height),
+            material,
+        }
+    }
+
+    /// Keeps only the part between heights `y_min` and `y_max` above the
+    /// lowest point.
+    pub fn with_height_limits(mut self, y_min: f64, y_max: f64) -> Self {
+        self.extent = self.extent.clip(y_min, y_max);
+        self
+    }
+
+    /// Keeps only `degrees` of the turn around the axis, starting from +x.
+    pub fn with_sweep(mut self, degrees: f64) -> Self {
+        self.extent = self.extent.sweep(degrees);
+        self
+    }
+
+    /// Leaves the ends open.
+    pub fn uncapped(mut self) -> Self {
+        self.extent.capped = false;
+        self
+    }
+
+    /// Radius of the bowl at height `y`.
+    fn radius_at(&self, y: f64) -> f64 {
+        self.radius * (y / self.height).sqrt()
+    }
+}
+
+impl HitTable for Paraboloid {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        // s (x^2 + z^2) = y
+        let (o, d) = (ray.origin() - self.center, ray.direction());
+        let s = self.height / (self.radius * self.radius);
+        let a = s * (d.x() * d.x() + d.z() * d.z());
+        let b = 2.0 * s * (o.x() * d.x() + o.z() * d.z()) - d.y();
+        let c = s * (o.x() * o.x() + o.z() * o.z()) - o.y();
+        let side = first_root(&solve_quadratic(a, b, c), t_min, t_max, |t| {
+            let p = o + t * d;
+            let phi = self.extent.contains(p)?;
+            let (u, v) = self.extent.side_uv(phi, p.y());
+            Some(SurfaceHit {
+                t,
+                p,
+                normal: Vec3::new(2.0 * s * p.x(), -1.0, 2.0 * s * p.z()),
+                u,
+                v,
+            })
+        });
+        let caps = self
+            .extent
+            .hit_caps(o, d, |y| self.radius_at(y), t_min, t_max);
+
+        match SurfaceHit::nearest(side, caps) {
+            Some(hit) => {
+                hit.record(ray, self.center, &self.material, rec);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let r = self.radius_at(self.extent.y_max);
+        *output_box = Aabb::new(
+            self.center + Vec3::new(-r, self.extent.y_min, -r),
+            self.center + Vec3::new(r, self.extent.y_max, r),
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paraboloid_test() {
+        let bowl = Paraboloid::new(Point3::new(0.0, 0.0, 0.0), 1.0, 1.0, MaterialType::None);
+        let mut rec = HitRecord::default();
+
+        let down = Ray::new(Point3::new(0.5, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(bowl.hit(&down, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 4.0);
+        assert_eq!(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+
+        // Open, the ray falls into the bowl and lands on its inside.
+        let open =
+            Paraboloid::new(Point3::new(0.0, 0.0, 0.0), 1.0, 1.0, MaterialType::None).uncapped();
+        assert!(open.hit(&down, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 4.75);
+        assert!((rec.normal - Vec3::new(-1.0, 1.0, 0.0).unit_vector()).near_zero());
+        assert!(!rec.front_face);
+
+        let under = Ray::new(Point3::new(0.0, -5.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert!(open.hit(&under, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 5.0);
+        assert!(rec.front_face);
+
+        let mut bbox = Aabb::default();
+        let cut = open.with_height_limits(0.0, 0.25);
+        assert!(cut.bounding_box(&mut bbox));
+        assert_eq!(bbox.max(), Point3::new(0.5, 0.25, 0.5));
+    }
+}
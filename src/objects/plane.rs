@@ -0,0 +1,99 @@
+use crate::core::aabb::Aabb;
+use crate::core::onb::Onb;
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, HitTable};
+use crate::objects::MaterialType;
+use crate::vec3::{Point3, Vec3};
+
+/// Infinite plane through `point`, facing `normal`. Texture coordinates are
+/// distances from `point` along two axes in the plane, so image textures
+/// clamp at their edges while solid textures such as checkers tile it.
+///
+/// Planes have no bounding box: a BVH tests them against every ray rather
+/// than placing them in its hierarchy.
+pub struct Plane {
+    point: Point3,
+    frame: Onb,
+    material: MaterialType,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vec3, material: MaterialType) -> Self {
+        Plane {
+            point,
+            frame: Onb::build_from_w(normal),
+            material,
+        }
+    }
+}
+
+impl HitTable for Plane {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let normal = self.frame.w();
+        let denom = Vec3::dot(normal, ray.direction());
+        if denom.abs() < 1e-8 {
+            return false;
+        }
+        let t = Vec3::dot(normal, self.point - ray.origin()) / denom;
+        if t < t_min || t_max < t {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = ray.at(t);
+        let planar = rec.p - self.point;
+        rec.u = Vec3::dot(planar, self.frame.u());
+        rec.v = Vec3::dot(planar, self.frame.v());
+        rec.material = &self.material;
+        rec.set_face_normal(ray, normal);
+
+        true
+    }
+
+    fn bounding_box(&self, _output_box: &mut Aabb) -> bool {
+        false
+    }
+}
+
+/// Density, per unit solid angle, of picking `direction` from `origin` by
+/// choosing a uniform point on the flat `shape` of the given `area`.
+pub(crate) fn area_light_pdf<T: HitTable + ?Sized>(
+    shape: &T,
+    area: f64,
+    origin: Point3,
+    direction: Vec3,
+) -> f64 {
+    let mut rec = HitRecord::default();
+    if !shape.hit(&Ray::new(origin, direction), 0.001, f64::INFINITY, &mut rec) {
+        return 0.0;
+    }
+    let distance_squared = rec.t * rec.t * direction.length_squared();
+    let cosine = (Vec3::dot(direction, rec.normal) / direction.length()).abs();
+
+    distance_squared / (cosine * area)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plane_test() {
+        let plane = Plane::new(
+            Point3::new(0.0, -1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            MaterialType::None,
+        );
+        let mut rec = HitRecord::default();
+        let ray = Ray::new(Point3::new(3.0, 1.0, 4.0), Vec3::new(1.0, -1.0, 0.0));
+        assert!(plane.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 2.0);
+        assert_eq!(rec.p, Point3::new(5.0, -1.0, 4.0));
+        assert_eq!(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+        assert!((rec.u * rec.u + rec.v * rec.v - 41.0).abs() < 1e-9);
+
+        let parallel = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(!plane.hit(&parallel, 0.001, f64::INFINITY, &mut rec));
+        assert!(!plane.bounding_box(&mut Aabb::default()));
+    }
+}
@@ -0,0 +1,159 @@
+use crate::core::aabb::Aabb;
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, HitTable};
+use crate::objects::plane::area_light_pdf;
+use crate::objects::triangle::intersect;
+use crate::objects::MaterialType;
+use crate::samplers::Sampler;
+use crate::vec3::{Point3, Vec3};
+
+const BOX_PADDING: f64 = 1e-4;
+
+/// Flat convex polygon, split into a fan of triangles around its first
+/// vertex. The normal follows the winding of the vertices, counterclockwise
+/// seen from the front. Texture coordinates span the polygon's bounding
+/// rectangle in its plane, with `u` along the first edge.
+pub struct Polygon {
+    vertices: Vec<Point3>,
+    normal: Vec3,
+    /// Running total of the fan triangles' areas, for picking one in
+    /// proportion to its size.
+    cumulative_area: Vec<f64>,
+    u_axis: Vec3,
+    v_axis: Vec3,
+    uv_origin: (f64, f64),
+    uv_extent: (f64, f64),
+    material: MaterialType,
+}
+
+impl Polygon {
+    /// # Panics
+    ///
+    /// Panics if there are fewer than three vertices or the polygon has no
+    /// area.
+    pub fn new(vertices: Vec<Point3>, material: MaterialType) -> Self {
+        assert!(vertices.len() >= 3, "a polygon needs at least 3 vertices");
+        let p0 = vertices[0];
+        let mut cumulative_area = Vec::with_capacity(vertices.len() - 2);
+        let mut total = 0.0;
+        let mut n = Vec3::default();
+        for pair in vertices[1..].windows(2) {
+            let cross = Vec3::cross(pair[0] - p0, pair[1] - p0);
+            total += 0.5 * cross.length();
+            cumulative_area.push(total);
+            n += cross;
+        }
+        assert!(total > 0.0, "a polygon needs a non-zero area");
+
+        let normal = n.unit_vector();
+        let u_axis = (vertices[1] - p0).unit_vector();
+        let v_axis = Vec3::cross(normal, u_axis);
+        let planar = |p: Point3| (Vec3::dot(p - p0, u_axis), Vec3::dot(p - p0, v_axis));
+        let (mut lo, mut hi) = ((0.0f64, 0.0f64), (0.0f64, 0.0f64));
+        for &p in &vertices {
+            let (x, y) = planar(p);
+            lo = (lo.0.min(x), lo.1.min(y));
+            hi = (hi.0.max(x), hi.1.max(y));
+        }
+
+        Polygon {
+            vertices,
+            normal,
+            cumulative_area,
+            u_axis,
+            v_axis,
+            uv_origin: lo,
+            uv_extent: (hi.0 - lo.0, hi.1 - lo.1),
+            material,
+        }
+    }
+
+    fn area(&self) -> f64 {
+        *self.cumulative_area.last().unwrap()
+    }
+}
+
+impl HitTable for Polygon {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let p0 = self.vertices[0];
+        let t = match self.vertices[1..]
+            .windows(2)
+            .find_map(|pair| intersect(p0, pair[0], pair[1], ray, t_min, t_max))
+        {
+            Some((t, _, _)) => t,
+            None => return false,
+        };
+
+        rec.t = t;
+        rec.p = ray.at(t);
+        let planar = rec.p - p0;
+        rec.u = (Vec3::dot(planar, self.u_axis) - self.uv_origin.0) / self.uv_extent.0;
+        rec.v = (Vec3::dot(planar, self.v_axis) - self.uv_origin.1) / self.uv_extent.1;
+        rec.material = &self.material;
+        rec.set_face_normal(ray, self.normal);
+
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let p0 = self.vertices[0];
+        *output_box = self
+            .vertices
+            .iter()
+            .fold(Aabb::new(p0, p0), |acc, &p| {
+                Aabb::surrounding_box(&acc, &Aabb::new(p, p))
+            })
+            .pad(BOX_PADDING);
+        true
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        area_light_pdf(self, self.area(), origin, direction)
+    }
+
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        let target = sampler.get_1d() * self.area();
+        let last = self.cumulative_area.len() - 1;
+        let i = self
+            .cumulative_area
+            .partition_point(|&a| a <= target)
+            .min(last);
+        let (a, b, c) = (self.vertices[0], self.vertices[i + 1], self.vertices[i + 2]);
+
+        let (s, t) = sampler.get_2d();
+        let su = s.sqrt();
+        let p = (1.0 - su) * a + su * (1.0 - t) * b + su * t * c;
+        p - origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polygon_test() {
+        // A unit square with a triangle on top, in the z = 0 plane.
+        let house = Polygon::new(
+            vec![
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(1.0, 1.0, 0.0),
+                Point3::new(0.5, 1.5, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+            ],
+            MaterialType::None,
+        );
+        assert_eq!(house.area(), 1.25);
+
+        let down = Vec3::new(0.0, 0.0, -1.0);
+        let mut rec = HitRecord::default();
+        let ray = Ray::new(Point3::new(0.5, 1.25, 1.0), down);
+        assert!(house.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert!((rec.u - 0.5).abs() < 1e-12 && (rec.v - 1.25 / 1.5).abs() < 1e-12);
+
+        let eave = Ray::new(Point3::new(0.1, 1.4, 1.0), down);
+        assert!(!house.hit(&eave, 0.001, f64::INFINITY, &mut rec));
+    }
+}
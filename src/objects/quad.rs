@@ -0,0 +1,115 @@
+use crate::core::aabb::Aabb;
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, HitTable};
+use crate::objects::plane::area_light_pdf;
+use crate::objects::MaterialType;
+use crate::samplers::Sampler;
+use crate::vec3::{Point3, Vec3};
+
+const BOX_PADDING: f64 = 1e-4;
+
+/// Parallelogram with corner `q` and edges `u` and `v`.
+pub struct Quad {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    material: MaterialType,
+    normal: Vec3,
+    d: f64,
+    w: Vec3,
+    area: f64,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vec3, v: Vec3, material: MaterialType) -> Self {
+        let n = Vec3::cross(u, v);
+        let normal = n.unit_vector();
+        Quad {
+            q,
+            u,
+            v,
+            material,
+            normal,
+            d: Vec3::dot(normal, q),
+            w: n / Vec3::dot(n, n),
+            area: n.length(),
+        }
+    }
+}
+
+impl HitTable for Quad {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let denom = Vec3::dot(self.normal, ray.direction());
+        if denom.abs() < 1e-8 {
+            return false;
+        }
+        let t = (self.d - Vec3::dot(self.normal, ray.origin())) / denom;
+        if t < t_min || t_max < t {
+            return false;
+        }
+
+        let intersection = ray.at(t);
+        let planar_hitpt = intersection - self.q;
+        let alpha = Vec3::dot(self.w, Vec3::cross(planar_hitpt, self.v));
+        let beta = Vec3::dot(self.w, Vec3::cross(self.u, planar_hitpt));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = intersection;
+        rec.u = alpha;
+        rec.v = beta;
+        rec.material = &self.material;
+        rec.set_face_normal(ray, self.normal);
+
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let diagonal1 = Aabb::new(self.q, self.q + self.u + self.v);
+        let diagonal2 = Aabb::new(self.q + self.u, self.q + self.v);
+        *output_box = Aabb::surrounding_box(&diagonal1, &diagonal2).pad(BOX_PADDING);
+        true
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        area_light_pdf(self, self.area, origin, direction)
+    }
+
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        let (s, t) = sampler.get_2d();
+        let p = self.q + s * self.u + t * self.v;
+        p - origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::samplers::{SamplerKind, SamplerType};
+    use std::f64::consts::PI;
+
+    #[test]
+    fn pdf_matches_random_test() {
+        let quad = Quad::new(
+            Point3::new(-1.0, -1.0, -1.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            MaterialType::None,
+        );
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        let mut sampler = SamplerType::new(SamplerKind::Sobol, 4096, 0);
+
+        let mut inverse_pdf_sum = 0.0;
+        for i in 0..4096 {
+            sampler.start_pixel_sample(0, 0, i);
+            let direction = quad.random(origin, &mut sampler);
+            inverse_pdf_sum += 1.0 / quad.pdf_value(origin, direction);
+        }
+        // A 2 by 2 square one unit away, seen from above its centre,
+        // subtends 4 asin(1 / 2) steradians.
+        let solid_angle = 2.0 * PI / 3.0;
+        assert!((inverse_pdf_sum / 4096.0 / solid_angle - 1.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,392 @@
+//! Pieces shared by the shapes of revolution around the y axis: cylinders,
+//! cones, paraboloids and tori. Each is described in its own frame, with
+//! `center` at the origin, and can be cut to a range of heights and a sweep
+//! of angles around the axis.
+
+use std::f64::consts::PI;
+use std::ops::{Deref, DerefMut};
+
+use crate::core::ray::Ray;
+use crate::core::HitRecord;
+use crate::objects::MaterialType;
+use crate::vec3::{Point3, Vec3};
+
+/// Below this a polynomial coefficient counts as zero.
+const EPSILON: f64 = 1e-9;
+
+/// The part of a shape that is kept: heights from `y_min` to `y_max`, and
+/// angles around the y axis from +x (0) towards +z up to `phi_max`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Extent {
+    pub y_min: f64,
+    pub y_max: f64,
+    /// In radians, from just above zero to a full turn.
+    pub phi_max: f64,
+    /// Whether flat caps close the ends.
+    pub capped: bool,
+}
+
+impl Extent {
+    pub fn new(y_min: f64, y_max: f64) -> Extent {
+        Extent {
+            y_min,
+            y_max,
+            phi_max: 2.0 * PI,
+            capped: true,
+        }
+    }
+
+    /// Narrows the height range to `y_min`..`y_max`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if nothing of the shape is left.
+    pub fn clip(self, y_min: f64, y_max: f64) -> Extent {
+        let (y_min, y_max) = (self.y_min.max(y_min), self.y_max.min(y_max));
+        assert!(y_min < y_max, "height limits leave nothing of the shape");
+        Extent {
+            y_min,
+            y_max,
+            ..self
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics unless `degrees` is in (0, 360].
+    pub fn sweep(self, degrees: f64) -> Extent {
+        assert!(
+            degrees > 0.0 && degrees <= 360.0,
+            "a sweep must be between 0 and 360 degrees"
+        );
+        Extent {
+            phi_max: degrees.to_radians(),
+            ..self
+        }
+    }
+
+    /// Angle of `p` around the axis when it lies within the extent.
+    pub fn contains(&self, p: Point3) -> Option<f64> {
+        if p.y() < self.y_min || p.y() > self.y_max {
+            return None;
+        }
+        let phi = angle_around_axis(p);
+        if phi > self.phi_max {
+            return None;
+        }
+        Some(phi)
+    }
+
+    /// Texture coordinates of a point at angle `phi` on the curved surface:
+    /// `u` around the sweep and `v` up the height range.
+    pub fn side_uv(&self, phi: f64, y: f64) -> (f64, f64) {
+        (
+            phi / self.phi_max,
+            (y - self.y_min) / (self.y_max - self.y_min),
+        )
+    }
+
+    /// Nearest hit on the flat caps closing the ends, if the shape is
+    /// capped, for a ray from `o` along `d` in shape space. `radius_at`
+    /// gives the radius of the shape at a height; ends that come to a point
+    /// get no cap.
+    pub fn hit_caps<F>(
+        &self,
+        o: Point3,
+        d: Vec3,
+        radius_at: F,
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<SurfaceHit>
+    where
+        F: Fn(f64) -> f64,
+    {
+        if !self.capped {
+            return None;
+        }
+        [self.y_min, self.y_max]
+            .into_iter()
+            .filter_map(|y| {
+                let radius = radius_at(y);
+                if radius <= 0.0 {
+                    return None;
+                }
+                self.hit_cap(o, d, y, radius, t_min, t_max)
+            })
+            .fold(None, |nearest, hit| SurfaceHit::nearest(nearest, Some(hit)))
+    }
+
+    /// Hits the cap of `radius` at height `y`, which faces up at the top of
+    /// the shape and down at the bottom.
+    fn hit_cap(
+        &self,
+        o: Point3,
+        d: Vec3,
+        y: f64,
+        radius: f64,
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<SurfaceHit> {
+        if d.y().abs() < EPSILON {
+            return None;
+        }
+        let t = (y - o.y()) / d.y();
+        if t < t_min || t > t_max {
+            return None;
+        }
+        let p = o + t * d;
+        let r = (p.x() * p.x() + p.z() * p.z()).sqrt();
+        if r > radius {
+            return None;
+        }
+        let phi = angle_around_axis(p);
+        if phi > self.phi_max {
+            return None;
+        }
+        Some(SurfaceHit {
+            t,
+            p,
+            normal: Vec3::new(0.0, if y == self.y_max { 1.0 } else { -1.0 }, 0.0),
+            u: phi / self.phi_max,
+            v: r / radius,
+        })
+    }
+}
+
+/// Angle of `p` around the y axis, from +x towards +z, in [0, 2 pi).
+pub(crate) fn angle_around_axis(p: Point3) -> f64 {
+    let phi = p.z().atan2(p.x());
+    if phi < 0.0 {
+        phi + 2.0 * PI
+    } else {
+        phi
+    }
+}
+
+/// A hit in shape space, with the outward normal.
+pub(crate) struct SurfaceHit {
+    pub t: f64,
+    pub p: Point3,
+    pub normal: Vec3,
+    pub u: f64,
+    pub v: f64,
+}
+
+impl SurfaceHit {
+    /// Keeps whichever of two hits is nearer.
+    pub fn nearest(a: Option<SurfaceHit>, b: Option<SurfaceHit>) -> Option<SurfaceHit> {
+        match (a, b) {
+            (Some(a), Some(b)) => Some(if b.t < a.t { b } else { a }),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Fills in `rec` for this hit of `ray` on a shape placed at `center`.
+    pub fn record<'a>(
+        self,
+        ray: &Ray,
+        center: Point3,
+        material: &'a MaterialType,
+        rec: &mut HitRecord<'a>,
+    ) {
+        rec.t = self.t;
+        rec.p = center + self.p;
+        rec.u = self.u;
+        rec.v = self.v;
+        rec.material = material;
+        rec.set_face_normal(ray, self.normal.unit_vector());
+    }
+}
+
+/// Nearest root within [`t_min`, `t_max`] of the surface equation whose
+/// point `accept` keeps, trying the roots in increasing order.
+pub(crate) fn first_root<F>(
+    roots: &[f64],
+    t_min: f64,
+    t_max: f64,
+    mut accept: F,
+) -> Option<SurfaceHit>
+where
+    F: FnMut(f64) -> Option<SurfaceHit>,
+{
+    roots
+        .iter()
+        .filter(|&&t| t >= t_min && t <= t_max)
+        .find_map(|&t| accept(t))
+}
+
+/// Up to four real roots of a polynomial, kept on the stack so that solving
+/// for every ray does not allocate.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Roots {
+    values: [f64; 4],
+    len: usize,
+}
+
+impl Roots {
+    fn push(&mut self, t: f64) {
+        self.values[self.len] = t;
+        self.len += 1;
+    }
+}
+
+impl Deref for Roots {
+    type Target = [f64];
+
+    fn deref(&self) -> &[f64] {
+        &self.values[..self.len]
+    }
+}
+
+impl DerefMut for Roots {
+    fn deref_mut(&mut self) -> &mut [f64] {
+        &mut self.values[..self.len]
+    }
+}
+
+/// Real roots of `a t^2 + b t + c`, in increasing order.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Roots {
+    let mut roots = Roots::default();
+    if a.abs() < EPSILON {
+        if b.abs() >= EPSILON {
+            roots.push(-c / b);
+        }
+        return roots;
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return roots;
+    }
+    // Avoids cancellation between -b and the square root.
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    let (t0, t1) = if q == 0.0 { (0.0, 0.0) } else { (q / a, c / q) };
+    roots.push(t0.min(t1));
+    roots.push(t0.max(t1));
+    roots
+}
+
+/// Real roots of `c[0] + c[1] t + c[2] t^2 + c[3] t^3`, unordered, following
+/// Schwarze's "Cubic and Quartic Roots" in Graphics Gems.
+fn solve_cubic(c: [f64; 4]) -> Roots {
+    // Normal form t^3 + a t^2 + b t + c, then t = y - a / 3 to remove the
+    // square term: y^3 + 3 p y + 2 q.
+    let (a, b, c) = (c[2] / c[3], c[1] / c[3], c[0] / c[3]);
+    let sq_a = a * a;
+    let p = (-sq_a / 3.0 + b) / 3.0;
+    let q = (2.0 / 27.0 * a * sq_a - a * b / 3.0 + c) / 2.0;
+    let cb_p = p * p * p;
+    let d = q * q + cb_p;
+
+    let mut roots = Roots::default();
+    if d.abs() < EPSILON {
+        if q.abs() < EPSILON {
+            roots.push(0.0);
+        } else {
+            let u = (-q).cbrt();
+            roots.push(2.0 * u);
+            roots.push(-u);
+        }
+    } else if d < 0.0 {
+        let phi = (-q / (-cb_p).sqrt()).clamp(-1.0, 1.0).acos() / 3.0;
+        let t = 2.0 * (-p).sqrt();
+        roots.push(t * phi.cos());
+        roots.push(-t * (phi + PI / 3.0).cos());
+        roots.push(-t * (phi - PI / 3.0).cos());
+    } else {
+        let sqrt_d = d.sqrt();
+        roots.push((sqrt_d - q).cbrt() - (sqrt_d + q).cbrt());
+    }
+    for y in roots.iter_mut() {
+        *y -= a / 3.0;
+    }
+    roots
+}
+
+/// Real roots of `c[0] + c[1] t + ... + c[4] t^4`, in increasing order, by
+/// Ferrari's method with each root then refined by Newton's method.
+pub fn solve_quartic(c: [f64; 5]) -> Roots {
+    if c[4].abs() < EPSILON {
+        let mut roots = solve_cubic([c[0], c[1], c[2], c[3]]);
+        roots.sort_by(f64::total_cmp);
+        return roots;
+    }
+    // Normal form t^4 + a t^3 + b t^2 + c t + d, then t = y - a / 4 to remove
+    // the cubic term: y^4 + p y^2 + q y + r.
+    let (a, b, cc, d) = (c[3] / c[4], c[2] / c[4], c[1] / c[4], c[0] / c[4]);
+    let sq_a = a * a;
+    let p = -3.0 / 8.0 * sq_a + b;
+    let q = sq_a * a / 8.0 - a * b / 2.0 + cc;
+    let r = -3.0 / 256.0 * sq_a * sq_a + sq_a * b / 16.0 - a * cc / 4.0 + d;
+
+    let mut roots = if r.abs() < EPSILON {
+        // y (y^3 + p y + q) = 0
+        let mut roots = solve_cubic([q, p, 0.0, 1.0]);
+        roots.push(0.0);
+        roots
+    } else {
+        // Any real root of the resolvent cubic splits the quartic into two
+        // quadratics.
+        let z = solve_cubic([r * p / 2.0 - q * q / 8.0, -r, -p / 2.0, 1.0])[0];
+        let u = z * z - r;
+        let v = 2.0 * z - p;
+        let root = |x: f64| {
+            if x.abs() < EPSILON {
+                Some(0.0)
+            } else if x > 0.0 {
+                Some(x.sqrt())
+            } else {
+                None
+            }
+        };
+        match (root(u), root(v)) {
+            (Some(u), Some(v)) => {
+                let v = if q < 0.0 { -v } else { v };
+                let mut roots = solve_quadratic(1.0, v, z - u);
+                for &t in solve_quadratic(1.0, -v, z + u).iter() {
+                    roots.push(t);
+                }
+                roots
+            }
+            _ => Roots::default(),
+        }
+    };
+
+    let value = |t: f64| (((c[4] * t + c[3]) * t + c[2]) * t + c[1]) * t + c[0];
+    let slope = |t: f64| ((4.0 * c[4] * t + 3.0 * c[3]) * t + 2.0 * c[2]) * t + c[1];
+    for t in roots.iter_mut() {
+        *t -= a / 4.0;
+        for _ in 0..2 {
+            let s = slope(*t);
+            if s != 0.0 {
+                *t -= value(*t) / s;
+            }
+        }
+    }
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solve_quartic_test() {
+        // (t - 1)(t - 2)(t + 3)(t - 0.5)
+        let roots = solve_quartic([-3.0, 9.5, -7.0, -0.5, 1.0].map(|c| c * 2.0));
+        let expected = [-3.0, 0.5, 1.0, 2.0];
+        assert_eq!(roots.len(), 4);
+        for (root, expected) in roots.iter().zip(expected) {
+            assert!((root - expected).abs() < 1e-9, "{:?}", roots);
+        }
+
+        // t^4 + 1 has no real roots; (t^2 - 4)(t^2 + 1) has two.
+        assert!(solve_quartic([1.0, 0.0, 0.0, 0.0, 1.0]).is_empty());
+        let roots = solve_quartic([-4.0, 0.0, -3.0, 0.0, 1.0]);
+        assert_eq!(roots.len(), 2);
+        assert!((roots[0] + 2.0).abs() < 1e-9 && (roots[1] - 2.0).abs() < 1e-9);
+
+        assert_eq!(*solve_quadratic(1.0, -3.0, 2.0), [1.0, 2.0]);
+        assert!(solve_quadratic(1.0, 0.0, 1.0).is_empty());
+    }
+}
@@ -1,6 +1,9 @@
+use crate::core::aabb::Aabb;
+use crate::core::onb::Onb;
 use crate::core::ray::Ray;
 use crate::core::{HitRecord, HitTable};
 use crate::objects::MaterialType;
+use crate::samplers::{uniform_sphere, Sampler};
 use crate::vec3::{Point3, Vec3};
 
 #[derive(Default)]
@@ -18,33 +21,199 @@ impl Sphere {
             material,
         }
     }
+
+    /// Uniformly samples a direction, around the z axis, within the cone
+    /// subtended by a sphere at the given squared distance.
+    fn random_to_sphere(radius_squared: f64, distance_squared: f64, u: (f64, f64)) -> Vec3 {
+        let (r1, r2) = u;
+        let cos_theta_max = (1.0 - radius_squared / distance_squared).sqrt();
+        let z = 1.0 + r2 * (cos_theta_max - 1.0);
+        let phi = 2.0 * std::f64::consts::PI * r1;
+        let sin_theta = (1.0 - z * z).sqrt();
+
+        Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z)
+    }
+
+    /// Maps a point on the unit sphere to (u, v) in [0, 1], with u running
+    /// around the y axis from -x and v from the south to the north pole.
+    pub fn get_sphere_uv(p: Point3) -> (f64, f64) {
+        let theta = (-p.y()).acos();
+        let phi = (-p.z()).atan2(p.x()) + std::f64::consts::PI;
+
+        (
+            phi / (2.0 * std::f64::consts::PI),
+            theta / std::f64::consts::PI,
+        )
+    }
 }
 
-impl HitTable for Sphere {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
-        let oc = ray.origin() - self.center;
-        let a = ray.direction().length_squared();
-        let half_b = Vec3::dot(oc, ray.direction());
-        let c = oc.length_squared() - self.radius * self.radius;
-
-        let discriminant = half_b * half_b - a * c;
-        if discriminant < 0.0 {
+/// Intersects `ray` with the sphere of `radius` around `center`, filling in
+/// `rec` for the nearest hit between `t_min` and `t_max`.
+pub(crate) fn hit_sphere<'a>(
+    center: Point3,
+    radius: f64,
+    material: &'a MaterialType,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+    rec: &mut HitRecord<'a>,
+) -> bool {
+    let (near, far) = match sphere_roots(center, radius, ray) {
+        Some(roots) => roots,
+        None => return false,
+    };
+    let mut root = near;
+    if root < t_min || t_max < root {
+        root = far;
+        if root < t_min || t_max < root {
             return false;
         }
-        let sqrtd = discriminant.sqrt();
-        let mut root = ((-half_b) - sqrtd) / a;
-        if root < t_min || t_max < root {
-            root = ((-half_b) + sqrtd) / a;
-            if root < t_min || t_max < root {
-                return false;
+    }
+    record_sphere_hit(center, radius, material, ray, root, rec);
+
+    true
+}
+
+/// Where `ray` enters and leaves the sphere, if it meets it at all.
+fn sphere_roots(center: Point3, radius: f64, ray: &Ray) -> Option<(f64, f64)> {
+    let oc = ray.origin() - center;
+    let a = ray.direction().length_squared();
+    let half_b = Vec3::dot(oc, ray.direction());
+    let c = oc.length_squared() - radius * radius;
+
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let sqrtd = discriminant.sqrt();
+    Some((((-half_b) - sqrtd) / a, ((-half_b) + sqrtd) / a))
+}
+
+fn record_sphere_hit<'a>(
+    center: Point3,
+    radius: f64,
+    material: &'a MaterialType,
+    ray: &Ray,
+    t: f64,
+    rec: &mut HitRecord<'a>,
+) {
+    rec.t = t;
+    rec.p = ray.at(rec.t);
+    rec.material = material;
+    let outward_normal = (rec.p - center) / radius;
+    rec.set_face_normal(ray, outward_normal);
+    let (u, v) = Sphere::get_sphere_uv(outward_normal);
+    rec.u = u;
+    rec.v = v;
+}
+
+impl HitTable for Sphere {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        hit_sphere(
+            self.center,
+            self.radius,
+            &self.material,
+            ray,
+            t_min,
+            t_max,
+            rec,
+        )
+    }
+
+    fn hit_all<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, hits: &mut Vec<HitRecord<'a>>) {
+        if let Some((near, far)) = sphere_roots(self.center, self.radius, ray) {
+            for t in [near, far] {
+                if t_min <= t && t <= t_max {
+                    let mut rec = HitRecord::default();
+                    record_sphere_hit(self.center, self.radius, &self.material, ray, t, &mut rec);
+                    hits.push(rec);
+                }
             }
         }
-        rec.t = root;
-        rec.p = ray.at(rec.t);
-        rec.material = self.material;
-        let outward_normal = (rec.p - self.center) / self.radius;
-        rec.set_face_normal(&ray, outward_normal);
+    }
 
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        *output_box = Aabb::new(self.center - r, self.center + r);
         true
     }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        let mut rec = HitRecord::default();
+        if !self.hit(&Ray::new(origin, direction), 0.001, f64::INFINITY, &mut rec) {
+            return 0.0;
+        }
+        let distance_squared = (self.center - origin).length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            // Seen from inside, directions are sampled over the whole sphere.
+            return 1.0 / (4.0 * std::f64::consts::PI);
+        }
+        let cos_theta_max = (1.0 - radius_squared / distance_squared).sqrt();
+        let solid_angle = 2.0 * std::f64::consts::PI * (1.0 - cos_theta_max);
+
+        1.0 / solid_angle
+    }
+
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        let direction = self.center - origin;
+        let distance_squared = direction.length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return uniform_sphere(sampler.get_2d());
+        }
+        let uvw = Onb::build_from_w(direction);
+        uvw.local(Sphere::random_to_sphere(
+            radius_squared,
+            distance_squared,
+            sampler.get_2d(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::samplers::{SamplerKind, SamplerType};
+    use std::f64::consts::PI;
+
+    fn assert_uv(p: Point3, u: f64, v: f64) {
+        let (actual_u, actual_v) = Sphere::get_sphere_uv(p);
+        assert!((actual_u - u).abs() < 1e-6, "u {} != {}", actual_u, u);
+        assert!((actual_v - v).abs() < 1e-6, "v {} != {}", actual_v, v);
+    }
+
+    #[test]
+    fn sphere_uv_test() {
+        assert_uv(Point3::new(1.0, 0.0, 0.0), 0.5, 0.5);
+        assert_uv(Point3::new(0.0, 0.0, 1.0), 0.25, 0.5);
+        assert_uv(Point3::new(0.0, 0.0, -1.0), 0.75, 0.5);
+
+        // The poles map to the bottom and top edges.
+        assert_eq!(Sphere::get_sphere_uv(Point3::new(0.0, -1.0, 0.0)).1, 0.0);
+        assert_eq!(Sphere::get_sphere_uv(Point3::new(0.0, 1.0, 0.0)).1, 1.0);
+
+        // The seam runs along -x, where u wraps from 1 back to 0.
+        assert_uv(Point3::new(-1.0, 0.0, 0.0), 0.0, 0.5);
+        assert_uv(Point3::new(-1.0, 0.0, -1e-9), 1.0, 0.5);
+        assert_uv(Point3::new(-1.0, 0.0, 1e-9), 0.0, 0.5);
+    }
+
+    #[test]
+    fn pdf_matches_random_test() {
+        let sphere = Sphere::new(0.0, 0.0, -4.0, 1.0, MaterialType::None);
+        let origin = Point3::new(0.5, 1.0, 0.0);
+        let mut sampler = SamplerType::new(SamplerKind::Sobol, 4096, 0);
+
+        let mut inverse_pdf_sum = 0.0;
+        for i in 0..4096 {
+            sampler.start_pixel_sample(0, 0, i);
+            let direction = sphere.random(origin, &mut sampler);
+            inverse_pdf_sum += 1.0 / sphere.pdf_value(origin, direction);
+        }
+        // Averaging 1 / pdf estimates the solid angle the sphere subtends.
+        let distance_squared = (Point3::new(0.0, 0.0, -4.0) - origin).length_squared();
+        let solid_angle = 2.0 * PI * (1.0 - (1.0 - 1.0 / distance_squared).sqrt());
+        assert!((inverse_pdf_sum / 4096.0 / solid_angle - 1.0).abs() < 0.01);
+    }
 }
@@ -0,0 +1,142 @@
+use std::f64::consts::PI;
+
+use crate::core::aabb::Aabb;
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, HitTable};
+use crate::objects::quadric::{first_root, solve_quartic, Extent, SurfaceHit};
+use crate::objects::MaterialType;
+use crate::vec3::{Point3, Vec3};
+
+/// Ring around the y axis through `center`: a tube of `minor_radius` whose
+/// middle runs `major_radius` from the axis. `u` goes around the axis and
+/// `v` around the tube, starting from its outer edge. Cuts are left open.
+pub struct Torus {
+    center: Point3,
+    major_radius: f64,
+    minor_radius: f64,
+    extent: Extent,
+    material: MaterialType,
+}
+
+impl Torus {
+    pub fn new(
+        center: Point3,
+        major_radius: f64,
+        minor_radius: f64,
+        material: MaterialType,
+    ) -> Self {
+        let mut extent = Extent::new(-minor_radius, minor_radius);
+        extent.capped = false;
+        Torus {
+            center,
+            major_radius,
+            minor_radius,
+            extent,
+            material,
+        }
+    }
+
+    /// Keeps only the part between heights `y_min` and `y_max` relative to
+    /// the centre.
+    pub fn with_height_limits(mut self, y_min: f64, y_max: f64) -> Self {
+        self.extent = self.extent.clip(y_min, y_max);
+        self
+    }
+
+    /// Keeps only `degrees` of the turn around the axis, starting from +x.
+    pub fn with_sweep(mut self, degrees: f64) -> Self {
+        self.extent = self.extent.sweep(degrees);
+        self
+    }
+}
+
+impl HitTable for Torus {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + z^2), solved along the unit
+        // direction to keep the coefficients well scaled.
+        let o = ray.origin() - self.center;
+        let length = ray.direction().length();
+        let d = ray.direction() / length;
+        let (r2, minor2) = (self.major_radius.powi(2), self.minor_radius.powi(2));
+        let e = o.length_squared() - r2 - minor2;
+        let f = Vec3::dot(o, d);
+        let mut roots = solve_quartic([
+            e * e - 4.0 * r2 * (minor2 - o.y() * o.y()),
+            4.0 * f * e + 8.0 * r2 * o.y() * d.y(),
+            2.0 * e + 4.0 * f * f + 4.0 * r2 * d.y() * d.y(),
+            4.0 * f,
+            1.0,
+        ]);
+        for t in roots.iter_mut() {
+            *t /= length;
+        }
+
+        let hit = first_root(&roots, t_min, t_max, |t| {
+            let p = o + t * ray.direction();
+            let phi = self.extent.contains(p)?;
+            let ring = Vec3::new(p.x(), 0.0, p.z()).unit_vector() * self.major_radius;
+            let across = (p.x() * p.x() + p.z() * p.z()).sqrt() - self.major_radius;
+            let theta = p.y().atan2(across);
+            Some(SurfaceHit {
+                t,
+                p,
+                normal: p - ring,
+                u: phi / self.extent.phi_max,
+                v: if theta < 0.0 { theta + 2.0 * PI } else { theta } / (2.0 * PI),
+            })
+        });
+
+        match hit {
+            Some(hit) => {
+                hit.record(ray, self.center, &self.material, rec);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let r = self.major_radius + self.minor_radius;
+        *output_box = Aabb::new(
+            self.center + Vec3::new(-r, self.extent.y_min, -r),
+            self.center + Vec3::new(r, self.extent.y_max, r),
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn torus_test() {
+        let torus = Torus::new(Point3::new(0.0, 0.0, 0.0), 2.0, 0.5, MaterialType::None);
+        let mut rec = HitRecord::default();
+
+        let side = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0));
+        assert!(torus.hit(&side, 0.001, f64::INFINITY, &mut rec));
+        assert!((rec.t - 1.25).abs() < 1e-9);
+        assert!((rec.normal - Vec3::new(-1.0, 0.0, 0.0)).near_zero());
+        assert!((rec.u - 0.5).abs() < 1e-9 && rec.v.abs() < 1e-9);
+
+        let down = Ray::new(Point3::new(2.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(torus.hit(&down, 0.001, f64::INFINITY, &mut rec));
+        assert!((rec.t - 4.5).abs() < 1e-9);
+        assert!((rec.normal - Vec3::new(0.0, 1.0, 0.0)).near_zero());
+        assert!((rec.v - 0.25).abs() < 1e-9);
+
+        // Straight down the hole.
+        let hole = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(!torus.hit(&hole, 0.001, f64::INFINITY, &mut rec));
+
+        // With only the first quarter kept, the ray passes the missing near
+        // side and enters the far one from the hole.
+        let quarter =
+            Torus::new(Point3::new(0.0, 0.0, 0.0), 2.0, 0.5, MaterialType::None).with_sweep(90.0);
+        assert!(quarter.hit(&side, 0.001, f64::INFINITY, &mut rec));
+        assert!((rec.t - 3.25).abs() < 1e-9);
+        assert!((rec.normal - Vec3::new(-1.0, 0.0, 0.0)).near_zero());
+        assert!(rec.front_face);
+    }
+}
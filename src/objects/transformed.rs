@@ -0,0 +1,230 @@
+use crate::core::aabb::Aabb;
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, HitTable};
+use crate::mat4::Mat4;
+use crate::samplers::Sampler;
+use crate::vec3::{Point3, Vec3};
+
+/// An object carried into the world by an affine transform. Rays are moved
+/// into the object's own space to be intersected, without normalising their
+/// direction so distances along them stay the same, and the hit is moved
+/// back out. Wrapping an `Arc` of a mesh instances it without copying its
+/// triangles.
+///
+/// The builders apply each transform after the ones before, so
+/// `Transformed::new(mesh).scale(s).rotate(axis, 90.0).translate(offset)`
+/// scales, then turns, then moves the mesh.
+pub struct Transformed<T> {
+    object: T,
+    /// Object to world.
+    transform: Mat4,
+    /// World to object.
+    inverse: Mat4,
+    /// Carries object space normals into the world: the transpose of
+    /// `inverse`.
+    normal_matrix: Mat4,
+}
+
+impl<T: HitTable> Transformed<T> {
+    /// The object where it is, ready for the builders below.
+    pub fn new(object: T) -> Self {
+        Transformed::with_matrix(object, Mat4::identity())
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `transform` cannot be inverted.
+    pub fn with_matrix(object: T, transform: Mat4) -> Self {
+        let inverse = transform.inverse().expect("a transform must be invertible");
+        Transformed {
+            object,
+            transform,
+            inverse,
+            normal_matrix: inverse.transpose(),
+        }
+    }
+
+    /// Applies `transform` after the current one.
+    pub fn then(self, transform: Mat4) -> Self {
+        Transformed::with_matrix(self.object, transform * self.transform)
+    }
+
+    pub fn translate(self, offset: Vec3) -> Self {
+        self.then(Mat4::translation(offset))
+    }
+
+    /// Turns by `degrees` about `axis` through the world origin.
+    pub fn rotate(self, axis: Vec3, degrees: f64) -> Self {
+        self.then(Mat4::rotation(axis, degrees))
+    }
+
+    /// Scales by `factors` along the world axes, about the world origin.
+    pub fn scale(self, factors: Vec3) -> Self {
+        self.then(Mat4::scaling(factors))
+    }
+
+    /// Moves the object's origin to `eye` and turns its z axis towards
+    /// `target`; see `Mat4::look_at`.
+    pub fn look_at(self, eye: Point3, target: Point3, vup: Vec3) -> Self {
+        self.then(Mat4::look_at(eye, target, vup))
+    }
+
+    fn to_local(&self, ray: &Ray) -> Ray {
+        Ray::with_time(
+            self.inverse.transform_point(ray.origin()),
+            self.inverse.transform_vector(ray.direction()),
+            ray.time(),
+        )
+    }
+
+    /// Moves a hit found in object space out to the world. The normal stays
+    /// on the same side of the ray, so `front_face` still holds.
+    fn to_world(&self, rec: &mut HitRecord) {
+        rec.p = self.transform.transform_point(rec.p);
+        rec.normal = self
+            .normal_matrix
+            .transform_vector(rec.normal)
+            .unit_vector();
+    }
+}
+
+impl<T: HitTable> HitTable for Transformed<T> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        if !self.object.hit(&self.to_local(ray), t_min, t_max, rec) {
+            return false;
+        }
+        self.to_world(rec);
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let mut local = Aabb::default();
+        if !self.object.bounding_box(&mut local) {
+            return false;
+        }
+        let (min, max) = (local.min(), local.max());
+        let mut corners = (0..8).map(|i| {
+            self.transform.transform_point(Point3::new(
+                if i & 1 == 0 { min.x() } else { max.x() },
+                if i & 2 == 0 { min.y() } else { max.y() },
+                if i & 4 == 0 { min.z() } else { max.z() },
+            ))
+        });
+        let first = corners.next().unwrap();
+        let (min, max) = corners.fold((first, first), |(min, max), p| {
+            (Vec3::minimum(min, p), Vec3::maximum(max, p))
+        });
+        *output_box = Aabb::new(min, max);
+        true
+    }
+
+    fn hit_surface<'a>(
+        &'a self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord<'a>,
+    ) -> bool {
+        if !self
+            .object
+            .hit_surface(&self.to_local(ray), t_min, t_max, rec)
+        {
+            return false;
+        }
+        self.to_world(rec);
+        true
+    }
+
+    fn hit_all<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, hits: &mut Vec<HitRecord<'a>>) {
+        let first = hits.len();
+        self.object.hit_all(&self.to_local(ray), t_min, t_max, hits);
+        for rec in &mut hits[first..] {
+            self.to_world(rec);
+        }
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        self.object.transmittance(&self.to_local(ray), t_min, t_max)
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        // A unit direction w in the world becomes A w in object space, where
+        // A is the linear part of `inverse`, and solid angle around it is
+        // scaled by |det A| / |A w|^3.
+        let w = direction.unit_vector();
+        let local = self.inverse.transform_vector(w);
+        let length = local.length();
+        let pdf = self
+            .object
+            .pdf_value(self.inverse.transform_point(origin), local / length);
+        pdf * self.inverse.determinant3().abs() / (length * length * length)
+    }
+
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        let local = self
+            .object
+            .random(self.inverse.transform_point(origin), sampler);
+        self.transform.transform_vector(local)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::axis_aligned_box::AxisAlignedBox;
+    use crate::objects::sphere::Sphere;
+    use crate::objects::MaterialType;
+    use crate::samplers::{SamplerKind, SamplerType};
+    use std::f64::consts::PI;
+
+    #[test]
+    fn transformed_test() {
+        // A unit cube stretched to 2 x 1 x 1, turned a quarter about y and
+        // moved 5 along x: it spans x 4.5..5.5 and z -1..1.
+        let cube = AxisAlignedBox::new(
+            Point3::new(-0.5, -0.5, -0.5),
+            Point3::new(0.5, 0.5, 0.5),
+            MaterialType::None,
+        );
+        let placed = Transformed::new(cube)
+            .scale(Vec3::new(2.0, 1.0, 1.0))
+            .rotate(Vec3::new(0.0, 1.0, 0.0), 90.0)
+            .translate(Vec3::new(5.0, 0.0, 0.0));
+        let mut rec = HitRecord::default();
+
+        let toward = Ray::new(Point3::new(5.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 2.0));
+        assert!(placed.hit(&toward, 0.001, f64::INFINITY, &mut rec));
+        assert!((rec.t - 2.0).abs() < 1e-9);
+        assert!((rec.p - Point3::new(5.0, 0.0, -1.0)).near_zero());
+        assert!((rec.normal - Vec3::new(0.0, 0.0, -1.0)).near_zero());
+        assert!(rec.front_face);
+
+        let beside = Ray::new(Point3::new(5.75, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(!placed.hit(&beside, 0.001, f64::INFINITY, &mut rec));
+
+        let mut bbox = Aabb::default();
+        assert!(placed.bounding_box(&mut bbox));
+        assert!((bbox.min() - Point3::new(4.5, -0.5, -1.0)).near_zero());
+        assert!((bbox.max() - Point3::new(5.5, 0.5, 1.0)).near_zero());
+    }
+
+    #[test]
+    fn transformed_light_test() {
+        // A unit sphere squashed to an ellipsoid with semi-axes 2, 1 and 1,
+        // seen from far away along y, covers about pi * 2 / d^2 steradians.
+        let sphere = Sphere::new(0.0, 0.0, 0.0, 1.0, MaterialType::None);
+        let ellipsoid = Transformed::new(sphere).scale(Vec3::new(2.0, 1.0, 1.0));
+        let mut sampler = SamplerType::new(SamplerKind::Sobol, 1024, 0);
+        let origin = Point3::new(0.0, 20.0, 0.0);
+        let mut solid_angle = 0.0;
+        for i in 0..1024 {
+            sampler.start_pixel_sample(0, 0, i);
+            let direction = ellipsoid.random(origin, &mut sampler);
+            let pdf = ellipsoid.pdf_value(origin, direction);
+            assert!(pdf > 0.0);
+            solid_angle += 1.0 / pdf / 1024.0;
+        }
+        let expected = 2.0 * PI / (20.0 * 20.0);
+        assert!((solid_angle - expected).abs() < 0.02 * expected);
+    }
+}
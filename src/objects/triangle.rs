@@ -0,0 +1,126 @@
+use crate::core::aabb::Aabb;
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, HitTable};
+use crate::objects::MaterialType;
+use crate::vec3::{Point3, Vec3};
+
+const EPSILON: f64 = 1e-12;
+const BOX_PADDING: f64 = 1e-4;
+
+pub struct Triangle {
+    vertices: [Point3; 3],
+    material: MaterialType,
+}
+
+impl Triangle {
+    pub fn new(p0: Point3, p1: Point3, p2: Point3, material: MaterialType) -> Self {
+        Triangle {
+            vertices: [p0, p1, p2],
+            material,
+        }
+    }
+}
+
+impl HitTable for Triangle {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let [p0, p1, p2] = self.vertices;
+        let (t, b1, b2) = match intersect(p0, p1, p2, ray, t_min, t_max) {
+            Some(hit) => hit,
+            None => return false,
+        };
+        rec.t = t;
+        rec.p = ray.at(t);
+        rec.u = b1;
+        rec.v = b2;
+        rec.barycentric = (b1, b2);
+        rec.material = &self.material;
+        let outward_normal = Vec3::cross(p1 - p0, p2 - p0).unit_vector();
+        rec.set_face_normal(ray, outward_normal);
+
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = triangle_box(self.vertices[0], self.vertices[1], self.vertices[2]);
+        true
+    }
+}
+
+/// Möller–Trumbore ray/triangle intersection.
+///
+/// Returns the ray parameter and the barycentric weights of `p1` and `p2`.
+pub fn intersect(
+    p0: Point3,
+    p1: Point3,
+    p2: Point3,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, f64, f64)> {
+    let edge1 = p1 - p0;
+    let edge2 = p2 - p0;
+    let pvec = Vec3::cross(ray.direction(), edge2);
+    let det = Vec3::dot(edge1, pvec);
+    if det.abs() < EPSILON {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let tvec = ray.origin() - p0;
+    let b1 = Vec3::dot(tvec, pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let qvec = Vec3::cross(tvec, edge1);
+    let b2 = Vec3::dot(ray.direction(), qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+    let t = Vec3::dot(edge2, qvec) * inv_det;
+    if t < t_min || t_max < t {
+        return None;
+    }
+
+    Some((t, b1, b2))
+}
+
+pub fn triangle_box(p0: Point3, p1: Point3, p2: Point3) -> Aabb {
+    Aabb::surrounding_box(&Aabb::new(p0, p1), &Aabb::new(p2, p2)).pad(BOX_PADDING)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hit_records_barycentrics_test() {
+        let triangle = Triangle::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+            MaterialType::None,
+        );
+        let ray = Ray::new(Point3::new(0.25, 0.5, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::default();
+
+        assert!(triangle.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.t, 1.0);
+        assert_eq!(rec.barycentric, (0.25, 0.5));
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn miss_outside_edges_test() {
+        let triangle = Triangle::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+            MaterialType::None,
+        );
+        let ray = Ray::new(Point3::new(0.75, 0.5, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::default();
+
+        assert!(!triangle.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+    }
+}
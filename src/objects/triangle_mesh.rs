@@ -0,0 +1,225 @@
+use std::sync::Arc;
+
+use crate::core::aabb::Aabb;
+use crate::core::ray::Ray;
+use crate::core::{HitRecord, HitTable};
+use crate::objects::bvh::BvhNode;
+use crate::objects::triangle;
+use crate::objects::MaterialType;
+use crate::vec3::{Point3, Vec3};
+
+/// Vertex and face data shared by every triangle of a mesh.
+pub struct MeshData {
+    positions: Vec<Point3>,
+    normals: Option<Vec<Vec3>>,
+    uvs: Option<Vec<(f64, f64)>>,
+    indices: Vec<[usize; 3]>,
+    material: MaterialType,
+}
+
+/// Indexed triangle mesh storing each vertex once.
+///
+/// Faces are kept in an internal `BvhNode`, so a mesh can be added to a world
+/// as a single hittable.
+pub struct TriangleMesh {
+    data: Arc<MeshData>,
+    bvh: Option<BvhNode>,
+}
+
+/// A single face of a `TriangleMesh`.
+pub struct MeshTriangle {
+    mesh: Arc<MeshData>,
+    face: usize,
+}
+
+impl TriangleMesh {
+    /// Builds a mesh from vertex positions and faces indexing into them.
+    ///
+    /// `normals` and `uvs`, when given, must have one entry per position.
+    pub fn new(
+        positions: Vec<Point3>,
+        indices: Vec<[usize; 3]>,
+        normals: Option<Vec<Vec3>>,
+        uvs: Option<Vec<(f64, f64)>>,
+        material: MaterialType,
+    ) -> TriangleMesh {
+        if let Some(normals) = &normals {
+            assert_eq!(
+                normals.len(),
+                positions.len(),
+                "one normal per vertex expected"
+            );
+        }
+        if let Some(uvs) = &uvs {
+            assert_eq!(uvs.len(), positions.len(), "one uv per vertex expected");
+        }
+        for face in indices.iter() {
+            for &index in face.iter() {
+                assert!(
+                    index < positions.len(),
+                    "vertex index {} out of range",
+                    index
+                );
+            }
+        }
+
+        let data = Arc::new(MeshData {
+            positions,
+            normals,
+            uvs,
+            indices,
+            material,
+        });
+        let faces: Vec<Arc<dyn HitTable>> = (0..data.indices.len())
+            .map(|face| {
+                Arc::new(MeshTriangle {
+                    mesh: data.clone(),
+                    face,
+                }) as Arc<dyn HitTable>
+            })
+            .collect();
+        let bvh = if faces.is_empty() {
+            None
+        } else {
+            Some(BvhNode::from_objects(faces))
+        };
+
+        TriangleMesh { data, bvh }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.indices.is_empty()
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = MeshTriangle> + '_ {
+        (0..self.len()).map(move |face| MeshTriangle {
+            mesh: self.data.clone(),
+            face,
+        })
+    }
+}
+
+impl HitTable for TriangleMesh {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        match &self.bvh {
+            Some(bvh) => bvh.hit(ray, t_min, t_max, rec),
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        match &self.bvh {
+            Some(bvh) => bvh.bounding_box(output_box),
+            None => false,
+        }
+    }
+}
+
+impl MeshTriangle {
+    fn vertices(&self) -> (Point3, Point3, Point3) {
+        let [i0, i1, i2] = self.mesh.indices[self.face];
+        let positions = &self.mesh.positions;
+        (positions[i0], positions[i1], positions[i2])
+    }
+}
+
+impl HitTable for MeshTriangle {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let (p0, p1, p2) = self.vertices();
+        let (t, b1, b2) = match triangle::intersect(p0, p1, p2, ray, t_min, t_max) {
+            Some(hit) => hit,
+            None => return false,
+        };
+        let b0 = 1.0 - b1 - b2;
+        let [i0, i1, i2] = self.mesh.indices[self.face];
+
+        rec.t = t;
+        rec.p = ray.at(t);
+        rec.barycentric = (b1, b2);
+        let (u, v) = match &self.mesh.uvs {
+            Some(uvs) => (
+                b0 * uvs[i0].0 + b1 * uvs[i1].0 + b2 * uvs[i2].0,
+                b0 * uvs[i0].1 + b1 * uvs[i1].1 + b2 * uvs[i2].1,
+            ),
+            None => (b1, b2),
+        };
+        rec.u = u;
+        rec.v = v;
+        rec.material = &self.mesh.material;
+
+        let geometric_normal = Vec3::cross(p1 - p0, p2 - p0).unit_vector();
+        rec.set_face_normal(ray, geometric_normal);
+        if let Some(normals) = &self.mesh.normals {
+            // Shade with the interpolated normal, kept on the side the ray arrived from.
+            let shading_normal =
+                (b0 * normals[i0] + b1 * normals[i1] + b2 * normals[i2]).unit_vector();
+            rec.normal = if Vec3::dot(shading_normal, rec.normal) < 0.0 {
+                -shading_normal
+            } else {
+                shading_normal
+            };
+        }
+
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let (p0, p1, p2) = self.vertices();
+        *output_box = triangle::triangle_box(p0, p1, p2);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::hittable_list::HitTableList;
+
+    #[test]
+    fn interpolates_normal_and_uv_test() {
+        let mesh = TriangleMesh::new(
+            vec![
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+            ],
+            vec![[0, 1, 2]],
+            Some(vec![
+                Vec3::new(0.0, 0.0, 1.0),
+                Vec3::new(1.0, 0.0, 1.0).unit_vector(),
+                Vec3::new(0.0, 1.0, 1.0).unit_vector(),
+            ]),
+            Some(vec![(0.0, 0.0), (1.0, 0.0), (0.5, 1.0)]),
+            MaterialType::None,
+        );
+        let ray = Ray::new(Point3::new(0.25, 0.5, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::default();
+
+        assert!(mesh.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!(rec.barycentric, (0.25, 0.5));
+        assert!((rec.u - 0.5).abs() < 1e-12);
+        assert!((rec.v - 0.5).abs() < 1e-12);
+        let expected = (0.25 * Vec3::new(0.0, 0.0, 1.0)
+            + 0.25 * Vec3::new(1.0, 0.0, 1.0).unit_vector()
+            + 0.5 * Vec3::new(0.0, 1.0, 1.0).unit_vector())
+        .unit_vector();
+        assert!((rec.normal - expected).length() < 1e-12);
+    }
+
+    #[test]
+    fn empty_mesh_in_bvh_test() {
+        let mesh = TriangleMesh::new(Vec::new(), Vec::new(), None, None, MaterialType::None);
+        assert!(mesh.is_empty());
+        let mut list = HitTableList::new();
+        list.add(mesh);
+        let bvh = BvhNode::new(list);
+
+        let ray = Ray::new(Point3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::default();
+        assert!(!bvh.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+    }
+}
@@ -0,0 +1,52 @@
+use std::io::prelude::*;
+
+use crate::vec3::Color;
+
+/// Writes linear radiance, in rows from top to bottom, as a little-endian
+/// colour Portable Float Map.
+pub fn write<W: Write>(
+    writer: &mut W,
+    width: u32,
+    height: u32,
+    pixels: &[Color],
+) -> std::io::Result<()> {
+    assert_eq!(
+        pixels.len(),
+        (width * height) as usize,
+        "pixel data size mismatch"
+    );
+
+    // A negative scale marks little-endian data.
+    writer.write_all(format!("PF\n{} {}\n-1.0\n", width, height).as_bytes())?;
+    // Rows are stored from the bottom of the image up.
+    for row in pixels.chunks(width as usize).rev() {
+        for color in row {
+            for i in 0..3 {
+                writer.write_all(&(color[i] as f32).to_le_bytes())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_header_test() {
+        let pixels = [Color::new(1.0, 2.0, 3.0), Color::new(4.0, 5.0, 6.0)];
+        let mut bytes = vec![];
+        write(&mut bytes, 1, 2, &pixels).unwrap();
+
+        let header = b"PF\n1 2\n-1.0\n";
+        assert_eq!(&bytes[..header.len()], header);
+        let values: Vec<f32> = bytes[header.len()..]
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        // The bottom row comes first.
+        assert_eq!(values, vec![4.0, 5.0, 6.0, 1.0, 2.0, 3.0]);
+    }
+}
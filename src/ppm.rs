@@ -1,26 +1,69 @@
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::prelude::*;
+use std::io::{BufWriter, Error, ErrorKind};
 use std::path::Path;
 
-use crate::core::clamp;
+use crate::tonemap::DisplayTransform;
 use crate::vec3::Color;
+use crate::{exr, pfm, png, radiance};
 
-#[derive(Debug, Clone)]
-pub enum Pixel {
-    Rgb([u8; 3]),
+/// Output file format, chosen from the file extension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    /// `.png`
+    Png,
+    /// `.ppm`, plain text P3.
+    PpmAscii,
+    /// `.pnm`, binary P6.
+    PpmBinary,
+    /// `.exr`, uncompressed 32-bit float OpenEXR.
+    Exr,
+    /// `.pfm`, Portable Float Map.
+    Pfm,
+    /// `.hdr`, Radiance RGBE.
+    Radiance,
 }
 
-impl Pixel {
-    pub fn zeros() -> Pixel {
-        Pixel::Rgb([0, 0, 0])
+impl ImageFormat {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> std::io::Result<ImageFormat> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("png") => Ok(ImageFormat::Png),
+            Some("ppm") => Ok(ImageFormat::PpmAscii),
+            Some("pnm") => Ok(ImageFormat::PpmBinary),
+            Some("exr") => Ok(ImageFormat::Exr),
+            Some("pfm") => Ok(ImageFormat::Pfm),
+            Some("hdr") => Ok(ImageFormat::Radiance),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "unsupported output format '{}': expected .png, .ppm, .pnm, .exr, .pfm or .hdr",
+                    path.display()
+                ),
+            )),
+        }
+    }
+
+    /// Whether the format stores linear floating point radiance.
+    pub fn is_hdr(&self) -> bool {
+        matches!(
+            self,
+            ImageFormat::Exr | ImageFormat::Pfm | ImageFormat::Radiance
+        )
     }
 }
 
+/// Framebuffer of linear radiance. Tone mapping to 8-bit display values only
+/// happens when saving to a low dynamic range format.
 #[derive(Debug)]
 pub struct Image {
     height: u32,
     width: u32,
-    buffer: Vec<Pixel>,
+    buffer: Vec<Color>,
 }
 
 impl Image {
@@ -28,42 +71,180 @@ impl Image {
         Image {
             height,
             width,
-            buffer: vec![Pixel::zeros(); (height * width) as usize],
+            buffer: vec![Color::new(0.0, 0.0, 0.0); (height * width) as usize],
         }
     }
 
-    pub fn set_pixel(&mut self, y: u32, x: u32, rgb: Pixel) {
-        self.buffer[(y * self.width + x) as usize] = rgb;
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixel(&self, y: u32, x: u32) -> Color {
+        self.buffer[(y * self.width + x) as usize]
+    }
+
+    pub fn set_pixel(&mut self, y: u32, x: u32, color: Color) {
+        self.buffer[(y * self.width + x) as usize] = color;
+    }
+
+    /// Writes the image in the format matching the extension of `path`.
+    /// `display` converts to 8-bit formats; float formats keep the linear
+    /// radiance untouched.
+    pub fn save<P: AsRef<Path>>(&self, path: P, display: &DisplayTransform) -> std::io::Result<()> {
+        let format = ImageFormat::from_path(&path)?;
+        let mut file = BufWriter::new(File::create(path)?);
+        match format {
+            ImageFormat::Png => {
+                png::write(&mut file, self.width, self.height, &self.rgb_bytes(display))?
+            }
+            ImageFormat::PpmAscii => {
+                file.write_all(b"P3\n")?;
+                file.write_all(format!("{} {}\n", &self.width, &self.height).as_bytes())?;
+                file.write_all(b"255\n")?;
+                for rgb in self.rgb_bytes(display).chunks(3) {
+                    file.write_all(format!("{} {} {}\n", rgb[0], rgb[1], rgb[2]).as_bytes())?;
+                }
+            }
+            ImageFormat::PpmBinary => {
+                file.write_all(format!("P6\n{} {}\n255\n", self.width, self.height).as_bytes())?;
+                file.write_all(&self.rgb_bytes(display))?;
+            }
+            ImageFormat::Exr => exr::write(&mut file, self.width, self.height, &self.buffer)?,
+            ImageFormat::Pfm => pfm::write(&mut file, self.width, self.height, &self.buffer)?,
+            ImageFormat::Radiance => {
+                radiance::write(&mut file, self.width, self.height, &self.buffer)?
+            }
+        }
+
+        file.flush()
+    }
+
+    fn rgb_bytes(&self, display: &DisplayTransform) -> Vec<u8> {
+        self.buffer
+            .iter()
+            .flat_map(|color| display.quantize(*color))
+            .collect()
     }
+}
+
+/// Reads an ASCII (P3) or binary (P6) PPM file, returning its width, height
+/// and pixels in rows from top to bottom, scaled to 8 bits.
+pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<(usize, usize, Vec<[u8; 3]>)> {
+    let bytes = fs::read(path)?;
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidData, message.to_string());
 
-    pub fn set_color(&mut self, y: u32, x: u32, color: &Color, samples_per_pixel: u32) {
-        let mut r = color.x();
-        let mut g = color.y();
-        let mut b = color.z();
-
-        let scale = 1.0 / samples_per_pixel as f64;
-        r = (scale * r).sqrt();
-        g = (scale * g).sqrt();
-        b = (scale * b).sqrt();
-
-        let rgb = Pixel::Rgb([
-            (256.0 * clamp(r, 0.0, 0.999)) as u8,
-            (256.0 * clamp(g, 0.0, 0.999)) as u8,
-            (256.0 * clamp(b, 0.0, 0.999)) as u8,
-        ]);
-        self.buffer[(y * self.width + x) as usize] = rgb;
+    let mut pos = 0;
+    let mut header = Vec::with_capacity(4);
+    while header.len() < 4 {
+        while pos < bytes.len() && (bytes[pos].is_ascii_whitespace() || bytes[pos] == b'#') {
+            if bytes[pos] == b'#' {
+                while pos < bytes.len() && bytes[pos] != b'\n' {
+                    pos += 1;
+                }
+            } else {
+                pos += 1;
+            }
+        }
+        let start = pos;
+        while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if start == pos {
+            return Err(invalid("truncated PPM header"));
+        }
+        header.push(String::from_utf8_lossy(&bytes[start..pos]).to_string());
+    }
+    let number = |s: &str| {
+        s.parse::<usize>()
+            .map_err(|_| invalid("invalid number in PPM header"))
+    };
+    let width = number(&header[1])?;
+    let height = number(&header[2])?;
+    let max_value = number(&header[3])?;
+    if max_value == 0 || max_value > 255 {
+        return Err(invalid("only 8-bit PPM images are supported"));
     }
+    let scale = |v: usize| (v * 255 / max_value).min(255) as u8;
 
-    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
-        let mut file = File::create(path)?;
-        file.write_all(b"P3\n")?;
-        file.write_all(format!("{} {}\n", &self.width, &self.height).as_bytes())?;
-        file.write_all(b"255\n")?;
-        for pixel in &self.buffer {
-            let Pixel::Rgb([r, g, b]) = pixel;
-            file.write_all(format!("{} {} {}\n", r, g, b).as_bytes())?;
+    let count = width * height;
+    let samples: Vec<usize> = match header[0].as_str() {
+        "P6" => {
+            let data = bytes.get(pos + 1..pos + 1 + 3 * count);
+            data.ok_or_else(|| invalid("truncated PPM pixel data"))?
+                .iter()
+                .map(|&v| v as usize)
+                .collect()
         }
+        "P3" => String::from_utf8_lossy(&bytes[pos..])
+            .split_whitespace()
+            .take(3 * count)
+            .map(number)
+            .collect::<std::io::Result<_>>()?,
+        _ => return Err(invalid("not a P3 or P6 PPM file")),
+    };
+    if samples.len() != 3 * count {
+        return Err(invalid("truncated PPM pixel data"));
+    }
 
-        Ok(())
+    Ok((
+        width,
+        height,
+        samples
+            .chunks(3)
+            .map(|c| [scale(c[0]), scale(c[1]), scale(c[2])])
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_format_test() {
+        assert_eq!(ImageFormat::from_path("a.png").unwrap(), ImageFormat::Png);
+        assert_eq!(ImageFormat::from_path("a.PNG").unwrap(), ImageFormat::Png);
+        assert_eq!(
+            ImageFormat::from_path("a.ppm").unwrap(),
+            ImageFormat::PpmAscii
+        );
+        assert_eq!(
+            ImageFormat::from_path("a.pnm").unwrap(),
+            ImageFormat::PpmBinary
+        );
+        assert_eq!(ImageFormat::from_path("a.exr").unwrap(), ImageFormat::Exr);
+        assert_eq!(ImageFormat::from_path("a.pfm").unwrap(), ImageFormat::Pfm);
+        assert_eq!(
+            ImageFormat::from_path("a.hdr").unwrap(),
+            ImageFormat::Radiance
+        );
+        assert!(ImageFormat::from_path("a.jpg").is_err());
+        assert!(ImageFormat::from_path("a").is_err());
+    }
+
+    #[test]
+    fn ascii_and_binary_round_trip_test() {
+        let mut image = Image::new(1, 2);
+        image.set_pixel(0, 0, Color::new(1.0, 0.0, 0.25));
+        image.set_pixel(0, 1, Color::new(0.0, 1.0, 0.0));
+        let display = DisplayTransform::default();
+        let dir = std::env::temp_dir();
+
+        for (extension, magic) in [("ppm", b"P3"), ("pnm", b"P6")] {
+            let path = dir.join(format!("round_trip_{}.{}", std::process::id(), extension));
+            image.save(&path, &display).unwrap();
+            let bytes = fs::read(&path).unwrap();
+            let loaded = load(&path);
+            fs::remove_file(&path).unwrap();
+
+            assert_eq!(&bytes[..2], magic);
+            let (width, height, pixels) = loaded.unwrap();
+            assert_eq!((width, height), (2, 1));
+            assert_eq!(pixels, vec![[255, 0, 128], [0, 255, 0]]);
+        }
     }
 }
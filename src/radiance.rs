@@ -0,0 +1,117 @@
+use std::io::prelude::*;
+
+use crate::vec3::Color;
+
+/// Writes linear radiance, in rows from top to bottom, as a run-length
+/// encoded Radiance RGBE (.hdr) image.
+pub fn write<W: Write>(
+    writer: &mut W,
+    width: u32,
+    height: u32,
+    pixels: &[Color],
+) -> std::io::Result<()> {
+    assert_eq!(
+        pixels.len(),
+        (width * height) as usize,
+        "pixel data size mismatch"
+    );
+
+    writer.write_all(b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n")?;
+    writer.write_all(format!("-Y {} +X {}\n", height, width).as_bytes())?;
+
+    let mut channels = vec![vec![0u8; width as usize]; 4];
+    for row in pixels.chunks(width as usize) {
+        // Run-length encoding is only defined for widths in 8..32768.
+        if !(8..0x8000).contains(&width) {
+            for color in row {
+                writer.write_all(&rgbe(color))?;
+            }
+            continue;
+        }
+        for (x, color) in row.iter().enumerate() {
+            for (channel, value) in channels.iter_mut().zip(rgbe(color)) {
+                channel[x] = value;
+            }
+        }
+        writer.write_all(&[2, 2, (width >> 8) as u8, width as u8])?;
+        for channel in &channels {
+            write_runs(writer, channel)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Shares one exponent between the three channels.
+fn rgbe(color: &Color) -> [u8; 4] {
+    let r = color.x().max(0.0);
+    let g = color.y().max(0.0);
+    let b = color.z().max(0.0);
+    let max = r.max(g).max(b);
+    if max < 1e-32 || !max.is_finite() {
+        return [0, 0, 0, 0];
+    }
+    let exponent = max.log2().floor() as i32 + 1;
+    let scale = 256.0 / 2f64.powi(exponent);
+    [
+        (r * scale).min(255.0) as u8,
+        (g * scale).min(255.0) as u8,
+        (b * scale).min(255.0) as u8,
+        (exponent + 128) as u8,
+    ]
+}
+
+/// Encodes one channel of a scanline as runs of repeated bytes (length
+/// above 128) and literal stretches (length up to 128).
+fn write_runs<W: Write>(writer: &mut W, data: &[u8]) -> std::io::Result<()> {
+    const MIN_RUN: usize = 4;
+
+    let mut i = 0;
+    while i < data.len() {
+        let mut run = 1;
+        while i + run < data.len() && run < 127 && data[i + run] == data[i] {
+            run += 1;
+        }
+        if run >= MIN_RUN {
+            writer.write_all(&[128 + run as u8, data[i]])?;
+            i += run;
+            continue;
+        }
+
+        // Collect literals up to the start of the next long enough run.
+        let start = i;
+        while i < data.len() && i - start < 128 {
+            let mut run = 1;
+            while i + run < data.len() && run < MIN_RUN && data[i + run] == data[i] {
+                run += 1;
+            }
+            if run >= MIN_RUN {
+                break;
+            }
+            i += 1;
+        }
+        writer.write_all(&[(i - start) as u8])?;
+        writer.write_all(&data[start..i])?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgbe_test() {
+        assert_eq!(rgbe(&Color::new(1.0, 0.5, 0.0)), [128, 64, 0, 129]);
+        assert_eq!(rgbe(&Color::new(0.0, 0.0, 0.0)), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn write_runs_test() {
+        let mut bytes = vec![];
+        write_runs(&mut bytes, &[7, 7, 7, 7, 7, 1, 2, 3]).unwrap();
+
+        assert_eq!(bytes, vec![133, 7, 3, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,71 @@
+use crate::filter::Filter;
+use crate::render::tile::Tile;
+use crate::vec3::Color;
+
+/// Filter-weighted sums of the samples taken in one tile. Samples reach
+/// pixels up to the filter radius away, so the film covers the tile plus a
+/// margin on every side, clipped to the image.
+pub struct Film {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Weighted colour and total weight of every pixel, row by row.
+    pub sums: Vec<(Color, f64)>,
+}
+
+impl Film {
+    pub fn new(tile: &Tile, filter: &Filter, image_width: u32, image_height: u32) -> Film {
+        // Samples lie inside their pixel, so the farthest centres they reach
+        // are the radius less half a pixel beyond its edges.
+        let margin = (filter.radius - 0.5).max(0.0).ceil() as u32;
+        let x = tile.x.saturating_sub(margin);
+        let y = tile.y.saturating_sub(margin);
+        let width = (tile.x + tile.width + margin).min(image_width) - x;
+        let height = (tile.y + tile.height + margin).min(image_height) - y;
+        Film {
+            x,
+            y,
+            width,
+            height,
+            sums: vec![(Color::new(0.0, 0.0, 0.0), 0.0); (width * height) as usize],
+        }
+    }
+
+    /// Adds `color`, sampled at image position (`x`, `y`) with y growing
+    /// downwards, to every pixel whose centre is within the filter radius.
+    pub fn add_sample(&mut self, filter: &Filter, x: f64, y: f64, color: Color) {
+        let range = |p: f64, start: u32, len: u32| {
+            let low = (p - 0.5 - filter.radius).floor().max(start as f64) as u32;
+            let high = (p - 0.5 + filter.radius)
+                .ceil()
+                .min((start + len - 1) as f64) as u32;
+            low..=high
+        };
+        for py in range(y, self.y, self.height) {
+            for px in range(x, self.x, self.width) {
+                let weight = filter.evaluate(x - (px as f64 + 0.5), y - (py as f64 + 0.5));
+                if weight != 0.0 {
+                    let sum = &mut self.sums[((py - self.y) * self.width + px - self.x) as usize];
+                    sum.0 += weight * color;
+                    sum.1 += weight;
+                }
+            }
+        }
+    }
+
+    /// The filtered value of image pixel (`x`, `y`) from this film alone.
+    pub fn pixel(&self, y: u32, x: u32) -> Color {
+        resolve(self.sums[((y - self.y) * self.width + x - self.x) as usize])
+    }
+}
+
+/// Divides a weighted sum by its total weight; pixels no sample reached, or
+/// whose negative weights cancel out, are black.
+pub fn resolve((color, weight): (Color, f64)) -> Color {
+    if weight == 0.0 {
+        Color::new(0.0, 0.0, 0.0)
+    } else {
+        color / weight
+    }
+}
@@ -0,0 +1,391 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use crate::core::background::Background;
+use crate::core::camera::Camera;
+use crate::core::fog::Fog;
+use crate::core::{ray_color, HitTable};
+use crate::objects::hittable_list::HitTableList;
+use crate::ppm::Image;
+use crate::samplers::{Sampler, SamplerKind, SamplerType};
+use crate::scene::{CameraSettings, ImageSettings};
+use crate::tonemap::luminance;
+use crate::vec3::Color;
+
+mod film;
+pub mod tile;
+
+use self::film::{resolve, Film};
+use self::tile::{tiles, Tile, TileOrder};
+
+/// Luminance below which adaptive sampling measures error against this
+/// value instead, so near-black pixels are not refined forever. It is less
+/// than one step of an 8-bit image.
+const MIN_LUMINANCE: f64 = 1.0 / 256.0;
+
+/// Renders a world into an `Image`. The frame is split into tiles which
+/// threads take in turn, render into a buffer of their own and send back to
+/// the calling thread. Buffers overlap where the filter spreads samples past
+/// a tile's edge, so they are summed in order of position once all are
+/// done, which keeps the image independent of the thread count and tile
+/// order.
+pub struct Renderer {
+    settings: ImageSettings,
+    camera: Camera,
+    background: Background,
+    fog: Fog,
+    threads: u32,
+    tile_size: u32,
+    tile_order: TileOrder,
+    sampler: SamplerKind,
+    seed: u64,
+}
+
+impl Renderer {
+    /// Creates a renderer using one thread per available CPU, 32 pixel tiles
+    /// in spiral order, clear air, the Sobol sampler and seed 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `settings` or `camera` fail validation.
+    pub fn new(settings: ImageSettings, camera: &CameraSettings, background: Background) -> Self {
+        if let Err((key, message)) = settings.validate() {
+            panic!("invalid image {}: {}", key, message);
+        }
+        if let Err((key, message)) = camera.validate() {
+            panic!("invalid camera {}: {}", key, message);
+        }
+        Renderer {
+            settings,
+            camera: camera.build(settings.aspect_ratio),
+            background,
+            fog: Fog::default(),
+            threads: thread::available_parallelism().map_or(1, |n| n.get() as u32),
+            tile_size: 32,
+            tile_order: TileOrder::Spiral,
+            sampler: SamplerKind::Sobol,
+            seed: 0,
+        }
+    }
+
+    pub fn with_threads(mut self, threads: u32) -> Self {
+        assert!(threads > 0, "at least one render thread is needed");
+        self.threads = threads;
+        self
+    }
+
+    pub fn with_tiles(mut self, size: u32, order: TileOrder) -> Self {
+        assert!(size > 0, "tile size must be positive");
+        self.tile_size = size;
+        self.tile_order = order;
+        self
+    }
+
+    pub fn with_fog(mut self, fog: Fog) -> Self {
+        self.fog = fog;
+        self
+    }
+
+    pub fn with_sampler(mut self, sampler: SamplerKind) -> Self {
+        self.sampler = sampler;
+        self
+    }
+
+    /// Seeds the random numbers of every pixel. The same seed gives the
+    /// same image whatever the thread count and tile order.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn settings(&self) -> &ImageSettings {
+        &self.settings
+    }
+
+    /// Renders `world`, sampling `lights` directly at every diffuse bounce.
+    /// `lights` may be empty.
+    pub fn render<T, L>(&self, world: &T, lights: &HitTableList<L>) -> Image
+    where
+        T: HitTable,
+        L: HitTable,
+    {
+        self.render_with_progress(world, lights, |_, _, _| {})
+    }
+
+    /// Like `render`, calling `progress` on the calling thread with the
+    /// image so far, the number of finished tiles and the total number of
+    /// tiles each time a tile is done.
+    pub fn render_with_progress<T, L, F>(
+        &self,
+        world: &T,
+        lights: &HitTableList<L>,
+        progress: F,
+    ) -> Image
+    where
+        T: HitTable,
+        L: HitTable,
+        F: FnMut(&Image, usize, usize),
+    {
+        self.render_with_sample_counts(world, lights, progress).0
+    }
+
+    /// Like `render_with_progress`, also returning the number of samples
+    /// taken by every pixel, row by row from the top.
+    pub fn render_with_sample_counts<T, L, F>(
+        &self,
+        world: &T,
+        lights: &HitTableList<L>,
+        mut progress: F,
+    ) -> (Image, Vec<u32>)
+    where
+        T: HitTable,
+        L: HitTable,
+        F: FnMut(&Image, usize, usize),
+    {
+        let width = self.settings.width;
+        let height = self.settings.height();
+        let tiles = tiles(width, height, self.tile_size, self.tile_order);
+        let next_tile = AtomicUsize::new(0);
+        let mut image = Image::new(height, width);
+        let mut sample_counts = vec![0; (width * height) as usize];
+        let mut films: Vec<Option<Film>> = tiles.iter().map(|_| None).collect();
+
+        thread::scope(|scope| {
+            let (sender, receiver) = mpsc::channel();
+            for _ in 0..self.threads.min(tiles.len() as u32) {
+                let sender = sender.clone();
+                let tiles = &tiles;
+                let next_tile = &next_tile;
+                scope.spawn(move || loop {
+                    let index = next_tile.fetch_add(1, Ordering::SeqCst);
+                    if index >= tiles.len() {
+                        break;
+                    }
+                    let (film, counts) = self.render_tile(&tiles[index], world, lights);
+                    if sender.send((index, film, counts)).is_err() {
+                        break;
+                    }
+                });
+            }
+            // Only the workers' senders are left, so the loop ends when they
+            // have all finished.
+            drop(sender);
+
+            for (done, (index, film, counts)) in receiver.iter().enumerate() {
+                // Until its neighbours are in, show the tile from its own
+                // samples alone.
+                let tile = tiles[index];
+                for (y, row) in counts.chunks(tile.width as usize).enumerate() {
+                    for (x, &samples) in row.iter().enumerate() {
+                        let (y, x) = (tile.y + y as u32, tile.x + x as u32);
+                        image.set_pixel(y, x, film.pixel(y, x));
+                        sample_counts[(y * width + x) as usize] = samples;
+                    }
+                }
+                films[index] = Some(film);
+                progress(&image, done + 1, tiles.len());
+            }
+        });
+
+        let mut sums = vec![(Color::new(0.0, 0.0, 0.0), 0.0); (width * height) as usize];
+        let mut films: Vec<Film> = films.into_iter().flatten().collect();
+        films.sort_by_key(|film| (film.y, film.x));
+        for film in &films {
+            for (y, row) in film.sums.chunks(film.width as usize).enumerate() {
+                for (x, &(color, weight)) in row.iter().enumerate() {
+                    let sum = &mut sums[((film.y + y as u32) * width + film.x + x as u32) as usize];
+                    sum.0 += color;
+                    sum.1 += weight;
+                }
+            }
+        }
+        for (index, &sum) in sums.iter().enumerate() {
+            image.set_pixel(index as u32 / width, index as u32 % width, resolve(sum));
+        }
+
+        (image, sample_counts)
+    }
+
+    /// Colours each pixel by the share of `samples_per_pixel` it took, from
+    /// dark blue for none through green to red for all of them. The values
+    /// are meant for display as they are, without tone mapping or gamma.
+    pub fn heatmap(&self, sample_counts: &[u32]) -> Image {
+        const RAMP: [(f64, f64, f64); 5] = [
+            (0.0, 0.0, 0.5),
+            (0.0, 0.5, 1.0),
+            (0.0, 1.0, 0.0),
+            (1.0, 1.0, 0.0),
+            (1.0, 0.0, 0.0),
+        ];
+        let width = self.settings.width;
+        let mut image = Image::new(self.settings.height(), width);
+        for (index, &samples) in sample_counts.iter().enumerate() {
+            let t = samples as f64 / self.settings.samples_per_pixel as f64;
+            let position = t.clamp(0.0, 1.0) * (RAMP.len() - 1) as f64;
+            let low = (position as usize).min(RAMP.len() - 2);
+            let f = position - low as f64;
+            let (a, b) = (RAMP[low], RAMP[low + 1]);
+            image.set_pixel(
+                index as u32 / width,
+                index as u32 % width,
+                Color::new(
+                    a.0 + f * (b.0 - a.0),
+                    a.1 + f * (b.1 - a.1),
+                    a.2 + f * (b.2 - a.2),
+                ),
+            );
+        }
+
+        image
+    }
+
+    /// Splats the samples of every pixel in `tile` into a film, returning it
+    /// with the number of samples each pixel took, row by row from the top.
+    /// With a noise threshold set, a pixel stops once the standard error of
+    /// its mean luminance, checked after every `min_samples_per_pixel`
+    /// samples, is below the threshold relative to that mean.
+    fn render_tile<T, L>(
+        &self,
+        tile: &Tile,
+        world: &T,
+        lights: &HitTableList<L>,
+    ) -> (Film, Vec<u32>)
+    where
+        T: HitTable,
+        L: HitTable,
+    {
+        let width = self.settings.width;
+        let height = self.settings.height();
+        let samples_per_pixel = self.settings.samples_per_pixel;
+        let threshold = self.settings.noise_threshold;
+        let batch = self.settings.min_samples_per_pixel.min(samples_per_pixel);
+        let filter = &self.settings.filter;
+        let mut film = Film::new(tile, filter, width, height);
+        let mut counts = Vec::with_capacity((tile.width * tile.height) as usize);
+        let mut sampler = SamplerType::new(self.sampler, samples_per_pixel, self.seed);
+
+        for y in tile.y..tile.y + tile.height {
+            // Camera coordinates grow upwards.
+            let j = height - 1 - y;
+            for i in tile.x..tile.x + tile.width {
+                // Running mean and sum of squared deviations of the luminance.
+                let (mut mean, mut m2) = (0.0, 0.0);
+                let mut taken = 0;
+                for sample in 0..samples_per_pixel {
+                    sampler.start_pixel_sample(i, y, sample);
+                    let (dx, dy) = sampler.get_2d();
+                    let u = (i as f64 + dx) / (width - 1) as f64;
+                    let v = (j as f64 + dy) / (height - 1) as f64;
+                    let r = self.camera.get_ray(u, v, &mut sampler);
+                    let color = ray_color(
+                        r,
+                        world,
+                        lights,
+                        &self.background,
+                        &self.fog,
+                        self.settings.max_depth,
+                        &mut sampler,
+                    );
+                    film.add_sample(filter, i as f64 + dx, (y + 1) as f64 - dy, color);
+                    taken = sample + 1;
+
+                    let l = luminance(color);
+                    let delta = l - mean;
+                    mean += delta / taken as f64;
+                    m2 += delta * (l - mean);
+                    if threshold > 0.0 && taken > 1 && taken % batch == 0 {
+                        let variance = m2 / (taken - 1).max(1) as f64;
+                        let error = (variance / taken as f64).sqrt();
+                        if error <= threshold * mean.max(MIN_LUMINANCE) {
+                            break;
+                        }
+                    }
+                }
+                counts.push(taken);
+            }
+        }
+
+        (film, counts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{Filter, FilterKind};
+    use crate::objects::lambertian::Lambertian;
+    use crate::objects::sphere::Sphere;
+    use crate::objects::MaterialType;
+
+    #[test]
+    fn seeded_render_is_reproducible_test() {
+        let mut world = HitTableList::new();
+        world.add(Sphere::new(
+            0.0,
+            0.0,
+            0.0,
+            2.0,
+            MaterialType::Lambertian(Lambertian::new(0.5, 0.5, 0.5)),
+        ));
+        let lights = HitTableList::<Sphere>::new();
+        let settings = ImageSettings {
+            width: 12,
+            aspect_ratio: 1.5,
+            samples_per_pixel: 2,
+            // Wide enough to splat across tile edges.
+            filter: Filter::new(FilterKind::Mitchell),
+            ..ImageSettings::default()
+        };
+        for sampler in [SamplerKind::Independent, SamplerKind::Halton] {
+            let renderer = || {
+                Renderer::new(settings, &CameraSettings::default(), Background::default())
+                    .with_sampler(sampler)
+                    .with_seed(3)
+            };
+
+            let a = renderer()
+                .with_threads(1)
+                .with_tiles(5, TileOrder::Scanline)
+                .render(&world, &lights);
+            let b = renderer()
+                .with_threads(3)
+                .with_tiles(5, TileOrder::Hilbert)
+                .render(&world, &lights);
+            for y in 0..a.height() {
+                for x in 0..a.width() {
+                    assert_eq!(a.pixel(y, x), b.pixel(y, x));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn adaptive_sampling_test() {
+        let mut world = HitTableList::new();
+        world.add(Sphere::new(
+            0.0,
+            0.0,
+            0.0,
+            2.0,
+            MaterialType::Lambertian(Lambertian::new(0.5, 0.5, 0.5)),
+        ));
+        let lights = HitTableList::<Sphere>::new();
+        let settings = ImageSettings {
+            width: 12,
+            aspect_ratio: 1.5,
+            samples_per_pixel: 64,
+            min_samples_per_pixel: 8,
+            noise_threshold: 0.05,
+            ..ImageSettings::default()
+        };
+        let renderer = Renderer::new(settings, &CameraSettings::default(), Background::default());
+
+        let (_, counts) = renderer.render_with_sample_counts(&world, &lights, |_, _, _| {});
+        // The sky has no variance and stops after the first batch, while
+        // pixels on the sphere keep sampling.
+        assert_eq!(counts[0], 8);
+        assert!(counts.iter().any(|&n| n > 8));
+        assert!(counts.iter().all(|&n| n % 8 == 0 && n <= 64));
+    }
+}
@@ -0,0 +1,161 @@
+/// Rectangle of pixels rendered as one unit of work, in image coordinates
+/// with `y` growing downwards.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Order in which tiles are handed out to the render threads.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TileOrder {
+    /// Left to right, top to bottom.
+    Scanline,
+    /// Outwards from the centre of the image, where the subject usually is.
+    Spiral,
+    /// Along a Hilbert curve, keeping consecutive tiles next to each other.
+    Hilbert,
+}
+
+impl TileOrder {
+    pub const NAMES: &'static [&'static str] = &["scanline", "spiral", "hilbert"];
+
+    pub fn from_name(name: &str) -> Option<TileOrder> {
+        match name {
+            "scanline" => Some(TileOrder::Scanline),
+            "spiral" => Some(TileOrder::Spiral),
+            "hilbert" => Some(TileOrder::Hilbert),
+            _ => None,
+        }
+    }
+}
+
+/// Splits a `width` by `height` image into tiles of at most `size` pixels
+/// square, listed in `order`.
+pub fn tiles(width: u32, height: u32, size: u32, order: TileOrder) -> Vec<Tile> {
+    assert!(size > 0, "tile size must be positive");
+    let columns = width.div_ceil(size);
+    let rows = height.div_ceil(size);
+
+    let cells: Vec<(u32, u32)> = match order {
+        TileOrder::Scanline => (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .collect(),
+        TileOrder::Spiral => spiral(columns, rows),
+        TileOrder::Hilbert => hilbert(columns, rows),
+    };
+
+    cells
+        .into_iter()
+        .map(|(column, row)| {
+            let x = column * size;
+            let y = row * size;
+            Tile {
+                x,
+                y,
+                width: size.min(width - x),
+                height: size.min(height - y),
+            }
+        })
+        .collect()
+}
+
+/// Walks a square spiral out from the centre cell, skipping cells outside
+/// the grid.
+fn spiral(columns: u32, rows: u32) -> Vec<(u32, u32)> {
+    let total = (columns * rows) as usize;
+    let mut cells = Vec::with_capacity(total);
+    let (mut x, mut y) = (((columns - 1) / 2) as i64, ((rows - 1) / 2) as i64);
+    let directions = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let mut leg = 0;
+
+    let visit = |x: i64, y: i64, cells: &mut Vec<(u32, u32)>| {
+        if (0..columns as i64).contains(&x) && (0..rows as i64).contains(&y) {
+            cells.push((x as u32, y as u32));
+        }
+    };
+    visit(x, y, &mut cells);
+    while cells.len() < total {
+        let (dx, dy) = directions[leg % 4];
+        // Legs grow by one every second turn: 1, 1, 2, 2, 3, 3, ...
+        for _ in 0..leg / 2 + 1 {
+            x += dx;
+            y += dy;
+            visit(x, y, &mut cells);
+        }
+        leg += 1;
+    }
+
+    cells
+}
+
+/// Visits the cells of the smallest power of two square covering the grid in
+/// Hilbert curve order, skipping cells outside the grid.
+fn hilbert(columns: u32, rows: u32) -> Vec<(u32, u32)> {
+    let n = columns.max(rows).next_power_of_two();
+    (0..n * n)
+        .map(|d| hilbert_point(n, d))
+        .filter(|&(x, y)| x < columns && y < rows)
+        .collect()
+}
+
+/// Converts distance `d` along the Hilbert curve filling an `n` by `n`
+/// square to coordinates.
+fn hilbert_point(n: u32, d: u32) -> (u32, u32) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+    let mut s = 1;
+    while s < n {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_cover_image_test() {
+        for order in [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert] {
+            let tiles = tiles(100, 70, 32, order);
+            assert_eq!(tiles.len(), 4 * 3);
+
+            let mut covered = vec![0; 100 * 70];
+            for tile in &tiles {
+                for y in tile.y..tile.y + tile.height {
+                    for x in tile.x..tile.x + tile.width {
+                        covered[(y * 100 + x) as usize] += 1;
+                    }
+                }
+            }
+            assert!(covered.iter().all(|&count| count == 1), "{:?}", order);
+        }
+    }
+
+    #[test]
+    fn tile_order_test() {
+        let spiral = tiles(96, 96, 32, TileOrder::Spiral);
+        assert_eq!((spiral[0].x, spiral[0].y), (32, 32));
+
+        let hilbert = tiles(128, 128, 32, TileOrder::Hilbert);
+        for pair in hilbert.windows(2) {
+            let distance = pair[0].x.abs_diff(pair[1].x) + pair[0].y.abs_diff(pair[1].y);
+            assert_eq!(distance, 32);
+        }
+    }
+}
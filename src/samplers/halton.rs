@@ -0,0 +1,75 @@
+use crate::core::rng::{mix_bits, Rng};
+use crate::samplers::{hash, permutation_element, Sampler, ONE_MINUS_EPSILON};
+
+const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// The Halton sequence, dimension `d` being the radical inverse in the `d`th
+/// prime base. Every pixel uses its own Owen scrambling of the digits, and
+/// dimensions past the prime table fall back to independent random numbers.
+pub struct HaltonSampler {
+    seed: u64,
+    pixel: (u32, u32),
+    index: u32,
+    dimension: u32,
+    rng: Rng,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> HaltonSampler {
+        HaltonSampler {
+            seed,
+            pixel: (0, 0),
+            index: 0,
+            dimension: 0,
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.pixel = (x, y);
+        self.index = index;
+        self.dimension = 0;
+        self.rng = Rng::new(hash(&[x as u64, y as u64, index as u64, self.seed]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.dimension as usize;
+        self.dimension += 1;
+        if dimension >= PRIMES.len() {
+            return self.rng.f64();
+        }
+        let (x, y) = self.pixel;
+        let h = hash(&[x as u64, y as u64, dimension as u64, self.seed]);
+        owen_scrambled_radical_inverse(PRIMES[dimension], self.index as u64, h)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.get_1d(), self.get_1d())
+    }
+}
+
+/// Mirrors the base `base` digits of `a` about the decimal point, permuting
+/// each digit by a hash of the digits before it.
+fn owen_scrambled_radical_inverse(base: u64, mut a: u64, hash: u64) -> f64 {
+    let limit = u64::MAX / base - base;
+    let inv_base = 1.0 / base as f64;
+    let mut inv_base_m = 1.0;
+    let mut reversed = 0u64;
+    // Keep going once `a` runs out of digits, so the scrambled zeros fill
+    // the remaining precision.
+    while 1.0 - (base - 1) as f64 * inv_base_m < 1.0 && reversed < limit {
+        let next = a / base;
+        let digit = a - next * base;
+        let digit_hash = mix_bits(hash ^ reversed) as u32;
+        let digit = permutation_element(digit as u32, base as u32, digit_hash) as u64;
+        reversed = reversed * base + digit;
+        inv_base_m *= inv_base;
+        a = next;
+    }
+    (inv_base_m * reversed as f64).min(ONE_MINUS_EPSILON)
+}
@@ -0,0 +1,31 @@
+use crate::core::rng::Rng;
+use crate::samplers::{hash, Sampler};
+
+/// Uniform random numbers with no stratification at all.
+pub struct IndependentSampler {
+    seed: u64,
+    rng: Rng,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> IndependentSampler {
+        IndependentSampler {
+            seed,
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.rng = Rng::new(hash(&[x as u64, y as u64, index as u64, self.seed]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.rng.f64()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.rng.f64(), self.rng.f64())
+    }
+}
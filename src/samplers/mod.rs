@@ -0,0 +1,238 @@
+pub mod halton;
+pub mod independent;
+pub mod sobol;
+pub mod stratified;
+
+use crate::core::rng::mix_bits;
+use crate::vec3::Vec3;
+
+use self::halton::HaltonSampler;
+use self::independent::IndependentSampler;
+use self::sobol::SobolSampler;
+use self::stratified::StratifiedSampler;
+
+/// Largest `f64` below one.
+pub const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0;
+
+/// Supplies the random numbers of one pixel sample at a time. Each call to
+/// `get_1d` or `get_2d` moves on to the next dimension, so a given decision
+/// along a path (pixel position, lens position, first bounce, ...) draws from
+/// the same dimension in every sample of the pixel, where the values are
+/// spread out better than independent random numbers.
+pub trait Sampler {
+    /// Starts sample `index` of pixel (`x`, `y`) from its first dimension.
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32);
+    fn get_1d(&mut self) -> f64;
+    fn get_2d(&mut self) -> (f64, f64);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SamplerKind {
+    /// Independent uniform random numbers.
+    Independent,
+    /// Jittered samples, one per stratum of each dimension.
+    Stratified,
+    /// Owen scrambled Halton sequence.
+    Halton,
+    /// Owen scrambled Sobol sequence, padded with shuffled 2D pairs.
+    Sobol,
+}
+
+impl SamplerKind {
+    pub const NAMES: &'static [&'static str] = &["independent", "stratified", "halton", "sobol"];
+
+    pub fn from_name(name: &str) -> Option<SamplerKind> {
+        match name {
+            "independent" => Some(SamplerKind::Independent),
+            "stratified" => Some(SamplerKind::Stratified),
+            "halton" => Some(SamplerKind::Halton),
+            "sobol" => Some(SamplerKind::Sobol),
+            _ => None,
+        }
+    }
+}
+
+pub enum SamplerType {
+    Independent(IndependentSampler),
+    Stratified(StratifiedSampler),
+    Halton(HaltonSampler),
+    Sobol(SobolSampler),
+}
+
+impl SamplerType {
+    /// Creates a sampler for pixels taking `samples_per_pixel` samples, with
+    /// every pixel scrambled differently according to `seed`.
+    pub fn new(kind: SamplerKind, samples_per_pixel: u32, seed: u64) -> SamplerType {
+        match kind {
+            SamplerKind::Independent => SamplerType::Independent(IndependentSampler::new(seed)),
+            SamplerKind::Stratified => {
+                SamplerType::Stratified(StratifiedSampler::new(samples_per_pixel, seed))
+            }
+            SamplerKind::Halton => SamplerType::Halton(HaltonSampler::new(seed)),
+            SamplerKind::Sobol => SamplerType::Sobol(SobolSampler::new(samples_per_pixel, seed)),
+        }
+    }
+}
+
+impl Sampler for SamplerType {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        match self {
+            SamplerType::Independent(s) => s.start_pixel_sample(x, y, index),
+            SamplerType::Stratified(s) => s.start_pixel_sample(x, y, index),
+            SamplerType::Halton(s) => s.start_pixel_sample(x, y, index),
+            SamplerType::Sobol(s) => s.start_pixel_sample(x, y, index),
+        }
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        match self {
+            SamplerType::Independent(s) => s.get_1d(),
+            SamplerType::Stratified(s) => s.get_1d(),
+            SamplerType::Halton(s) => s.get_1d(),
+            SamplerType::Sobol(s) => s.get_1d(),
+        }
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        match self {
+            SamplerType::Independent(s) => s.get_2d(),
+            SamplerType::Stratified(s) => s.get_2d(),
+            SamplerType::Halton(s) => s.get_2d(),
+            SamplerType::Sobol(s) => s.get_2d(),
+        }
+    }
+}
+
+/// Hashes a pixel, dimension and seed into a scrambling seed.
+pub(crate) fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0, |h, &v| {
+        mix_bits(h ^ v.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+    })
+}
+
+/// Element `i` of a pseudo-random permutation of `0..n` chosen by `seed`,
+/// following Kensler's "Correlated Multi-Jittered Sampling".
+pub(crate) fn permutation_element(mut i: u32, n: u32, seed: u32) -> u32 {
+    let p = seed;
+    let mut w = n - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < n {
+            break;
+        }
+    }
+    ((i as u64 + p as u64) % n as u64) as u32
+}
+
+/// Maps a point of the unit square to a uniformly distributed unit vector.
+pub fn uniform_sphere(u: (f64, f64)) -> Vec3 {
+    let z = 1.0 - 2.0 * u.0;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * std::f64::consts::PI * u.1;
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+/// Maps a point of the unit cube to a uniformly distributed point in the
+/// unit ball.
+pub fn uniform_ball(u: (f64, f64), r: f64) -> Vec3 {
+    uniform_sphere(u) * r.cbrt()
+}
+
+/// Maps a point of the unit square to a uniformly distributed point of the
+/// unit disk in the xy plane, keeping neighbouring points close together.
+pub fn concentric_disk(u: (f64, f64)) -> Vec3 {
+    let (a, b) = (2.0 * u.0 - 1.0, 2.0 * u.1 - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return Vec3::new(0.0, 0.0, 0.0);
+    }
+    let quarter_pi = std::f64::consts::FRAC_PI_4;
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, quarter_pi * (b / a))
+    } else {
+        (b, 2.0 * quarter_pi - quarter_pi * (a / b))
+    };
+    Vec3::new(r * theta.cos(), r * theta.sin(), 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples_2d(kind: SamplerKind, count: u32, skip: u32) -> Vec<(f64, f64)> {
+        let mut sampler = SamplerType::new(kind, count, 1);
+        (0..count)
+            .map(|i| {
+                sampler.start_pixel_sample(3, 4, i);
+                for _ in 0..skip {
+                    sampler.get_1d();
+                }
+                sampler.get_2d()
+            })
+            .collect()
+    }
+
+    /// Number of samples in each cell of a `columns` by `rows` grid.
+    fn cell_counts(samples: &[(f64, f64)], columns: usize, rows: usize) -> Vec<usize> {
+        let mut counts = vec![0; columns * rows];
+        for &(x, y) in samples {
+            assert!((0.0..1.0).contains(&x) && (0.0..1.0).contains(&y));
+            counts[(y * rows as f64) as usize * columns + (x * columns as f64) as usize] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn stratification_test() {
+        let stratified = samples_2d(SamplerKind::Stratified, 16, 1);
+        assert_eq!(cell_counts(&stratified, 4, 4), vec![1; 16]);
+
+        // Halton dimensions 0 and 1 use bases 2 and 3.
+        let halton = samples_2d(SamplerKind::Halton, 18, 0);
+        assert_eq!(cell_counts(&halton, 2, 9), vec![1; 18]);
+
+        // Scrambled Sobol points form a (0, 2)-net: one per elementary interval.
+        let sobol = samples_2d(SamplerKind::Sobol, 16, 1);
+        for (columns, rows) in [(16, 1), (8, 2), (4, 4), (2, 8), (1, 16)] {
+            assert_eq!(cell_counts(&sobol, columns, rows), vec![1; 16]);
+        }
+
+        let independent = samples_2d(SamplerKind::Independent, 16, 1);
+        assert_eq!(cell_counts(&independent, 1, 1), vec![16]);
+    }
+
+    #[test]
+    fn permutation_element_test() {
+        let mut seen: Vec<u32> = (0..10).map(|i| permutation_element(i, 10, 1234)).collect();
+        seen.sort();
+        assert_eq!(seen, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn warp_test() {
+        assert!((uniform_sphere((0.3, 0.7)).length() - 1.0).abs() < 1e-12);
+        assert!(uniform_ball((0.3, 0.7), 0.5).length() < 1.0);
+        assert!(concentric_disk((0.99, 0.01)).length() <= 1.0);
+    }
+}
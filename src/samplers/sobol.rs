@@ -0,0 +1,91 @@
+use crate::core::rng::mix_bits;
+use crate::samplers::{hash, permutation_element, Sampler, ONE_MINUS_EPSILON};
+
+/// Padded Sobol sampling: every 1D or 2D request takes the first one or two
+/// Sobol dimensions, Owen scrambled and with the sample order shuffled by a
+/// hash of the pixel and dimension, so requests stay uncorrelated with each
+/// other while each one is as well stratified as a (0, 2)-sequence.
+pub struct SobolSampler {
+    samples_per_pixel: u32,
+    seed: u64,
+    pixel: (u32, u32),
+    index: u32,
+    dimension: u32,
+}
+
+impl SobolSampler {
+    pub fn new(samples_per_pixel: u32, seed: u64) -> SobolSampler {
+        SobolSampler {
+            samples_per_pixel,
+            seed,
+            pixel: (0, 0),
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Scrambling hash and shuffled sample index for the current dimension.
+    fn next_dimension(&mut self) -> (u64, u32) {
+        let (x, y) = self.pixel;
+        let h = hash(&[x as u64, y as u64, self.dimension as u64, self.seed]);
+        self.dimension += 1;
+        let index = if self.index < self.samples_per_pixel {
+            permutation_element(self.index, self.samples_per_pixel, h as u32)
+        } else {
+            self.index
+        };
+        (h, index)
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.pixel = (x, y);
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (h, index) = self.next_dimension();
+        to_unit(owen_scramble(index.reverse_bits(), (h >> 32) as u32))
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (h, index) = self.next_dimension();
+        (
+            to_unit(owen_scramble(index.reverse_bits(), (h >> 32) as u32)),
+            to_unit(owen_scramble(sobol_second(index), mix_bits(h) as u32)),
+        )
+    }
+}
+
+/// Second dimension of the Sobol sequence, whose generator matrix is Pascal's
+/// triangle modulo two.
+fn sobol_second(mut a: u32) -> u32 {
+    let mut v = 1u32 << 31;
+    let mut result = 0;
+    while a != 0 {
+        if a & 1 != 0 {
+            result ^= v;
+        }
+        a >>= 1;
+        v ^= v >> 1;
+    }
+    result
+}
+
+/// Fast approximation of Owen scrambling: flips each bit depending on a hash
+/// of the bits above it.
+fn owen_scramble(v: u32, seed: u32) -> u32 {
+    let mut v = v.reverse_bits();
+    v ^= v.wrapping_mul(0x3d20_adea);
+    v = v.wrapping_add(seed);
+    v = v.wrapping_mul((seed >> 16) | 1);
+    v ^= v.wrapping_mul(0x0552_6c56);
+    v ^= v.wrapping_mul(0x53a2_2864);
+    v.reverse_bits()
+}
+
+fn to_unit(v: u32) -> f64 {
+    (v as f64 / (1u64 << 32) as f64).min(ONE_MINUS_EPSILON)
+}
@@ -0,0 +1,64 @@
+use crate::core::rng::Rng;
+use crate::samplers::{hash, permutation_element, Sampler};
+
+/// Jittered sampling: each dimension is split into one stratum per sample
+/// (a grid of strata for 2D), and the samples of a pixel visit the strata in
+/// an order shuffled independently for every dimension.
+pub struct StratifiedSampler {
+    samples_per_pixel: u32,
+    x_strata: u32,
+    y_strata: u32,
+    seed: u64,
+    pixel: (u32, u32),
+    index: u32,
+    dimension: u32,
+    rng: Rng,
+}
+
+impl StratifiedSampler {
+    pub fn new(samples_per_pixel: u32, seed: u64) -> StratifiedSampler {
+        let x_strata = (samples_per_pixel as f64).sqrt().ceil() as u32;
+        StratifiedSampler {
+            samples_per_pixel,
+            x_strata,
+            y_strata: samples_per_pixel.div_ceil(x_strata),
+            seed,
+            pixel: (0, 0),
+            index: 0,
+            dimension: 0,
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Stratum of the current sample among `count` strata.
+    fn stratum(&mut self, count: u32) -> u32 {
+        let (x, y) = self.pixel;
+        let h = hash(&[x as u64, y as u64, self.dimension as u64, self.seed]);
+        permutation_element(self.index % count, count, h as u32)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.pixel = (x, y);
+        self.index = index;
+        self.dimension = 0;
+        self.rng = Rng::new(hash(&[x as u64, y as u64, index as u64, self.seed]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let stratum = self.stratum(self.samples_per_pixel);
+        self.dimension += 1;
+        (stratum as f64 + self.rng.f64()) / self.samples_per_pixel as f64
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let stratum = self.stratum(self.x_strata * self.y_strata);
+        self.dimension += 2;
+        let (sx, sy) = (stratum % self.x_strata, stratum / self.x_strata);
+        (
+            (sx as f64 + self.rng.f64()) / self.x_strata as f64,
+            (sy as f64 + self.rng.f64()) / self.y_strata as f64,
+        )
+    }
+}
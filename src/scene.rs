@@ -0,0 +1,1939 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::core::aabb::Aabb;
+use crate::core::background::Background;
+use crate::core::camera::Camera;
+use crate::core::fog::Fog;
+use crate::core::rng::Rng;
+use crate::core::HitTable;
+use crate::filter::{Filter, FilterKind};
+use crate::json::{self, Json};
+use crate::mat4::Mat4;
+use crate::obj::{self, ObjError};
+use crate::objects::axis_aligned_box::AxisAlignedBox;
+use crate::objects::bvh::BvhNode;
+use crate::objects::cone::Cone;
+use crate::objects::constant_medium::ConstantMedium;
+use crate::objects::csg::{Csg, CsgOperation};
+use crate::objects::cylinder::Cylinder;
+use crate::objects::dielectric::Dielectric;
+use crate::objects::diffuse_light::DiffuseLight;
+use crate::objects::disk::Disk;
+use crate::objects::grid_medium::{DensityGrid, GridMedium};
+use crate::objects::henyey_greenstein::phase_function;
+use crate::objects::hittable_list::HitTableList;
+use crate::objects::lambertian::Lambertian;
+use crate::objects::metal::Metal;
+use crate::objects::moving_sphere::MovingSphere;
+use crate::objects::paraboloid::Paraboloid;
+use crate::objects::plane::Plane;
+use crate::objects::polygon::Polygon;
+use crate::objects::quad::Quad;
+use crate::objects::sphere::Sphere;
+use crate::objects::torus::Torus;
+use crate::objects::transformed::Transformed;
+use crate::objects::triangle::Triangle;
+use crate::objects::triangle_mesh::TriangleMesh;
+use crate::objects::MaterialType;
+use crate::textures::checker::Checker;
+use crate::textures::image_texture::ImageTexture;
+use crate::textures::noise::{Noise, NoiseKind};
+use crate::textures::TextureType;
+use crate::tonemap::{DisplayTransform, ToneMapper, Transfer};
+use crate::vec3::{Point3, Vec3};
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io {
+        path: String,
+        error: std::io::Error,
+    },
+    Json {
+        file: String,
+        error: json::ParseError,
+    },
+    Invalid {
+        key: String,
+        message: String,
+    },
+    Obj(ObjError),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io { path, error } => write!(f, "{}: {}", path, error),
+            SceneError::Json { file, error } => write!(f, "{}:{}", file, error),
+            SceneError::Invalid { key, message } => write!(f, "{}: {}", key, message),
+            SceneError::Obj(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<ObjError> for SceneError {
+    fn from(error: ObjError) -> Self {
+        SceneError::Obj(error)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImageSettings {
+    pub width: u32,
+    pub aspect_ratio: f64,
+    /// Samples per pixel, or the most any pixel takes when sampling is
+    /// adaptive.
+    pub samples_per_pixel: u32,
+    /// Samples every pixel takes before adaptive sampling may stop it.
+    pub min_samples_per_pixel: u32,
+    /// Relative standard error of a pixel's luminance below which adaptive
+    /// sampling stops taking samples. Zero disables adaptive sampling.
+    pub noise_threshold: f64,
+    pub max_depth: u32,
+    pub filter: Filter,
+    pub display: DisplayTransform,
+}
+
+impl ImageSettings {
+    pub fn height(&self) -> u32 {
+        (self.width as f64 / self.aspect_ratio) as u32
+    }
+
+    /// Checks the image is large enough to render, returning the offending
+    /// key and a message otherwise.
+    pub fn validate(&self) -> Result<(), (&'static str, &'static str)> {
+        if self.width < 2 {
+            return Err(("width", "must be at least 2 pixels"));
+        }
+        if self.height() < 2 {
+            return Err(("aspect_ratio", "image height would be less than 2 pixels"));
+        }
+        if self.min_samples_per_pixel == 0 {
+            return Err(("min_samples_per_pixel", "must be at least 1"));
+        }
+        if self.noise_threshold.is_nan() || self.noise_threshold < 0.0 {
+            return Err(("noise_threshold", "must not be negative"));
+        }
+        if !(self.filter.radius > 0.0 && self.filter.radius.is_finite()) {
+            return Err(("filter_radius", "must be greater than zero"));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for ImageSettings {
+    fn default() -> Self {
+        ImageSettings {
+            width: 1200,
+            aspect_ratio: 3.0 / 2.0,
+            samples_per_pixel: 500,
+            min_samples_per_pixel: 16,
+            noise_threshold: 0.0,
+            max_depth: 50,
+            filter: Filter::default(),
+            display: DisplayTransform::default(),
+        }
+    }
+}
+
+/// Parameters of `Camera::new`, apart from the aspect ratio which comes from
+/// the image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraSettings {
+    pub lookfrom: Point3,
+    pub lookat: Point3,
+    pub vup: Vec3,
+    pub vfov: f64,
+    pub aperture: f64,
+    pub focus_dist: f64,
+    /// Times at which the shutter opens and closes.
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+}
+
+impl CameraSettings {
+    /// Checks the settings describe a usable view, returning the offending
+    /// key and a message otherwise.
+    pub fn validate(&self) -> Result<(), (&'static str, &'static str)> {
+        if self.vfov <= 0.0 || self.vfov >= 180.0 {
+            return Err(("vfov", "must be between 0 and 180 degrees"));
+        }
+        if self.lookfrom == self.lookat {
+            return Err(("lookat", "must differ from lookfrom"));
+        }
+        if Vec3::cross(self.vup, self.lookfrom - self.lookat).near_zero() {
+            return Err(("vup", "must not be parallel to the view direction"));
+        }
+        if self.shutter_close < self.shutter_open {
+            return Err(("shutter_close", "must not be before shutter_open"));
+        }
+
+        Ok(())
+    }
+
+    pub fn build(&self, aspect_ratio: f64) -> Camera {
+        Camera::new(
+            self.lookfrom,
+            self.lookat,
+            self.vup,
+            self.vfov,
+            aspect_ratio,
+            self.aperture,
+            self.focus_dist,
+        )
+        .with_shutter(self.shutter_open, self.shutter_close)
+    }
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        CameraSettings {
+            lookfrom: Point3::new(13.0, 2.0, 3.0),
+            lookat: Point3::new(0.0, 0.0, 0.0),
+            vup: Vec3::new(0.0, 1.0, 0.0),
+            vfov: 20.0,
+            aperture: 0.1,
+            focus_dist: 10.0,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+        }
+    }
+}
+
+/// The final scene of "Ray Tracing in One Weekend": a field of small random
+/// spheres around three large ones on a ground plane, rendered when no scene
+/// file is given.
+pub fn random_scene(rng: &mut Rng) -> HitTableList<Box<dyn HitTable>> {
+    let mut world: HitTableList<Box<dyn HitTable>> = HitTableList::new();
+
+    world.add(Box::new(Plane::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        MaterialType::Lambertian(Lambertian::new(0.5, 0.5, 0.5)),
+    )));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat = rng.f64();
+            let center = Point3::new(a as f64 + 0.9 * rng.f64(), 0.2, b as f64 + 0.9 * rng.f64());
+
+            if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
+                if choose_mat < 0.8 {
+                    let albedo = Vec3::random_vec3(rng) * Vec3::random_vec3(rng);
+                    world.add(Box::new(Sphere::new(
+                        center.x(),
+                        center.y(),
+                        center.z(),
+                        0.2,
+                        MaterialType::Lambertian(Lambertian::new(
+                            albedo.x(),
+                            albedo.y(),
+                            albedo.z(),
+                        )),
+                    )));
+                } else if choose_mat < 0.95 {
+                    let albedo = Vec3::random_vec3(rng);
+                    let fuzz = rng.range_f64(0.0, 0.5);
+                    world.add(Box::new(Sphere::new(
+                        center.x(),
+                        center.y(),
+                        center.z(),
+                        0.2,
+                        MaterialType::Metal(Metal::new(albedo.x(), albedo.y(), albedo.z(), fuzz)),
+                    )));
+                } else {
+                    world.add(Box::new(Sphere::new(
+                        center.x(),
+                        center.y(),
+                        center.z(),
+                        0.2,
+                        MaterialType::Dielectric(Dielectric::new(1.5)),
+                    )));
+                }
+            }
+        }
+    }
+    world.add(Box::new(Sphere::new(
+        0.0,
+        1.0,
+        0.0,
+        1.0,
+        MaterialType::Dielectric(Dielectric::new(1.5)),
+    )));
+    world.add(Box::new(Sphere::new(
+        -4.0,
+        1.0,
+        0.0,
+        1.0,
+        MaterialType::Lambertian(Lambertian::new(0.4, 0.2, 0.1)),
+    )));
+    world.add(Box::new(Sphere::new(
+        4.0,
+        1.0,
+        0.0,
+        1.0,
+        MaterialType::Metal(Metal::new(0.7, 0.6, 0.5, 0.0)),
+    )));
+
+    world
+}
+
+/// A scene read from a JSON description.
+///
+/// ```json
+/// {
+///   "image": { "width": 400, "aspect_ratio": 1.5, "samples_per_pixel": 100, "max_depth": 50,
+///              "noise_threshold": 0.02, "filter": "mitchell", "exposure": 0.5, "tone_map": "aces",
+///              "transfer": "srgb" },
+///   "camera": { "lookfrom": [13, 2, 3], "lookat": [0, 0, 0], "vup": [0, 1, 0],
+///               "vfov": 20, "aperture": 0.1, "focus_dist": 10, "shutter_open": 0, "shutter_close": 1 },
+///   "background": { "type": "gradient", "bottom": [1, 1, 1], "top": [0.5, 0.7, 1] },
+///   "fog": { "density": 0.01, "albedo": [0.9, 0.9, 0.9] },
+///   "materials": {
+///     "ground": { "type": "lambertian", "albedo": { "type": "checker", "scale": 0.5,
+///                 "even": [0.2, 0.3, 0.1], "odd": [0.9, 0.9, 0.9] } },
+///     "marble": { "type": "lambertian", "albedo": { "type": "noise", "kind": "marble", "scale": 4 } },
+///     "glass": { "type": "dielectric", "ir": 1.5 },
+///     "lamp": { "type": "diffuse_light", "emit": [4, 4, 4] }
+///   },
+///   "objects": [
+///     { "type": "plane", "point": [0, 0, 0], "normal": [0, 1, 0], "material": "ground" },
+///     { "type": "sphere", "center": [0, 1, 0], "radius": 1, "material": "glass" },
+///     { "type": "moving_sphere", "center0": [2, 0.5, 2], "center1": [2, 1, 2], "radius": 0.5,
+///       "material": "marble" },
+///     { "type": "quad", "q": [-1, 5, -1], "u": [2, 0, 0], "v": [0, 0, 2], "material": "lamp" },
+///     { "type": "disk", "center": [0, 5, -4], "normal": [0, -1, 1], "radius": 1, "inner_radius": 0.5,
+///       "material": "lamp" },
+///     { "type": "polygon", "vertices": [[-4, 0, -3], [-3, 0, -3], [-3.5, 1, -3]], "material": "marble" },
+///     { "type": "box", "min": [3, 0, -3], "max": [4, 2, -2], "rotate": [0, 15, 0], "material": "ground" },
+///     { "type": "cylinder", "center": [-4, 0, 0], "radius": 0.5, "height": 2, "sweep": 270,
+///       "capped": false, "material": "marble" },
+///     { "type": "torus", "center": [0, 0.25, 3], "major_radius": 1, "minor_radius": 0.25,
+///       "material": "marble" },
+///     { "type": "csg", "operation": "intersection",
+///       "a": { "type": "sphere", "center": [2, 1, 0.6], "radius": 1, "material": "glass" },
+///       "b": { "type": "sphere", "center": [2, 1, -0.6], "radius": 1, "material": "glass" } },
+///     { "type": "constant_medium", "boundary": { "type": "sphere", "center": [-2, 1, 2], "radius": 1 },
+///       "density": 0.5, "albedo": [0.8, 0.8, 0.8] },
+///     { "type": "volume", "min": [3, 0, -1], "max": [5, 2, 1], "density": 4, "albedo": [1, 1, 1],
+///       "anisotropy": 0.6, "grid": { "type": "noise", "resolution": [64, 64, 64], "scale": 4 } },
+///     { "type": "mesh", "file": "teapot.obj" },
+///     { "type": "mesh", "file": "teapot.obj", "scale": 0.5, "rotate": [0, 90, 0], "translate": [2, 0, 3] }
+///   ]
+/// }
+/// ```
+///
+/// `image`, `camera`, `background` and each of their keys are optional and
+/// default to the built-in random scene settings. The image display transform
+/// takes an `exposure` in stops, a `tone_map` (`clamp`, `reinhard`,
+/// `reinhard_extended` with `white_point`, `aces` or `hable`) and a `transfer`
+/// (`srgb`, or `gamma` with `gamma`). The background is `black`,
+/// `solid` with a `color`, or a `gradient`. Material albedos are colour
+/// arrays or `solid`, `checker`, `noise` (with an optional integer `seed`) or
+/// `image` (PPM file) textures. Moving spheres go from `center0` at `time0`
+/// (default 0) to `center1` at `time1` (default 1), or through a list of
+/// `keyframes`, each an object with a `time` and a `center`. A `plane` is
+/// infinite, a `disk` becomes an annulus with an `inner_radius`, and a
+/// `polygon` takes the corners of a flat convex polygon in order. A `box`
+/// spans `min` to `max` and is turned about its centre by the optional
+/// `rotate` degrees around x, then y, then z. A `cylinder`, `cone` or
+/// `paraboloid` stands on `center` and rises `height` along y, with its
+/// `radius` at the base, the base or the top respectively, and a `torus`
+/// lies flat around `center` with a `major_radius` to the middle of a tube
+/// of `minor_radius`. Each can be cut to heights `y_min` to `y_max` above
+/// `center` and to a `sweep` in degrees around the axis from +x towards +z;
+/// all but the torus are closed by flat caps unless `capped` is false. A
+/// `csg` object combines two closed solids `a` and `b` (spheres, boxes,
+/// the shapes above or further `csg` objects) by `union`, `intersection`
+/// or `difference`, which keeps what is inside `a` but not `b`. The
+/// optional `fog` fills the scene, and a `constant_medium` its `boundary`
+/// sphere or box, with a medium of constant `density`. A `volume` spreads a
+/// grid of densities, scaled by `density`, over the box from `min` to
+/// `max`; the grid is read from a `raw` `file` of little-endian 32-bit
+/// floats at the given `resolution`, x fastest, or generated from `noise`.
+/// Media scatter isotropically unless given a Henyey-Greenstein
+/// `anisotropy` between -1 and 1. A `mesh` may be placed by a `scale`
+/// (a number or one per axis), then a `rotate` in degrees around x, then y,
+/// then z, then a `translate`; meshes placed this way share the triangles
+/// of their file, so one model can be instanced many times. Relative mesh,
+/// texture and grid paths are resolved against the directory of the scene
+/// file.
+pub struct Scene {
+    pub image: ImageSettings,
+    pub camera: CameraSettings,
+    pub background: Background,
+    pub fog: Fog,
+    pub world: HitTableList<Box<dyn HitTable>>,
+    /// Emissive spheres, quads, disks and polygons, sampled directly when
+    /// shading.
+    pub lights: HitTableList<Arc<dyn HitTable>>,
+    /// Statements the mesh files use that were skipped, for the caller to
+    /// report.
+    pub warnings: Vec<String>,
+}
+
+impl Scene {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Scene, SceneError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|error| SceneError::Io {
+            path: path.display().to_string(),
+            error,
+        })?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        Scene::parse(&source, &path.display().to_string(), dir)
+    }
+
+    /// Parses a scene description. `file` is only used in error messages and
+    /// `dir` is the directory mesh files are resolved against.
+    pub fn parse(source: &str, file: &str, dir: &Path) -> Result<Scene, SceneError> {
+        let root = json::parse(source).map_err(|error| SceneError::Json {
+            file: file.to_string(),
+            error,
+        })?;
+        let root = Section::new(
+            &root,
+            "scene",
+            &[
+                "image",
+                "camera",
+                "background",
+                "fog",
+                "materials",
+                "objects",
+            ],
+        )?;
+
+        let image = match root.get("image") {
+            Some(value) => parse_image(value)?,
+            None => ImageSettings::default(),
+        };
+        let camera = match root.get("camera") {
+            Some(value) => parse_camera(value)?,
+            None => CameraSettings::default(),
+        };
+        let background = match root.get("background") {
+            Some(value) => parse_background(value)?,
+            None => Background::default(),
+        };
+        let fog = match root.get("fog") {
+            Some(value) => parse_fog(value)?,
+            None => Fog::default(),
+        };
+        let materials = match root.get("materials") {
+            Some(value) => parse_materials(value, dir)?,
+            None => HashMap::new(),
+        };
+        let objects = match root.get("objects") {
+            Some(Json::Array(objects)) => objects,
+            Some(value) => return Err(root.type_error("objects", "an array", value)),
+            None => return Err(root.missing("objects")),
+        };
+
+        let mut world: HitTableList<Box<dyn HitTable>> = HitTableList::new();
+        let mut lights: HitTableList<Arc<dyn HitTable>> = HitTableList::new();
+        let mut meshes = MeshLoader::default();
+        for (i, value) in objects.iter().enumerate() {
+            parse_object(
+                value,
+                &format!("objects[{}]", i),
+                &materials,
+                dir,
+                &mut meshes,
+                &mut world,
+                &mut lights,
+            )?;
+        }
+        if world.is_empty() {
+            return Err(SceneError::Invalid {
+                key: "objects".to_string(),
+                message: "scene has no objects".to_string(),
+            });
+        }
+
+        Ok(Scene {
+            image,
+            camera,
+            background,
+            fog,
+            world,
+            lights,
+            warnings: meshes.warnings,
+        })
+    }
+}
+
+fn parse_image(value: &Json) -> Result<ImageSettings, SceneError> {
+    let section = Section::new(
+        value,
+        "image",
+        &[
+            "width",
+            "aspect_ratio",
+            "samples_per_pixel",
+            "min_samples_per_pixel",
+            "noise_threshold",
+            "max_depth",
+            "filter",
+            "filter_radius",
+            "exposure",
+            "tone_map",
+            "white_point",
+            "transfer",
+            "gamma",
+        ],
+    )?;
+    let defaults = ImageSettings::default();
+    let display = DisplayTransform {
+        exposure: section
+            .number("exposure")?
+            .unwrap_or(defaults.display.exposure),
+        tone_mapper: match section.string("tone_map")? {
+            Some(name) => ToneMapper::from_name(name).ok_or_else(|| {
+                section.invalid(
+                    "tone_map",
+                    &format!(
+                        "unknown tone mapper '{}', expected one of: {}",
+                        name,
+                        ToneMapper::NAMES.join(", ")
+                    ),
+                )
+            })?,
+            None => defaults.display.tone_mapper,
+        },
+        white_point: section
+            .positive("white_point")?
+            .unwrap_or(defaults.display.white_point),
+        transfer: match section.string("transfer")? {
+            Some(name) => Transfer::from_name(name).ok_or_else(|| {
+                section.invalid(
+                    "transfer",
+                    &format!(
+                        "unknown transfer function '{}', expected one of: {}",
+                        name,
+                        Transfer::NAMES.join(", ")
+                    ),
+                )
+            })?,
+            None => defaults.display.transfer,
+        },
+        gamma: section.positive("gamma")?.unwrap_or(defaults.display.gamma),
+    };
+    let mut filter = match section.string("filter")? {
+        Some(name) => Filter::new(FilterKind::from_name(name).ok_or_else(|| {
+            section.invalid(
+                "filter",
+                &format!(
+                    "unknown filter '{}', expected one of: {}",
+                    name,
+                    FilterKind::NAMES.join(", ")
+                ),
+            )
+        })?),
+        None => defaults.filter,
+    };
+    filter.radius = section.positive("filter_radius")?.unwrap_or(filter.radius);
+    let image = ImageSettings {
+        width: section.integer("width")?.unwrap_or(defaults.width),
+        aspect_ratio: section
+            .positive("aspect_ratio")?
+            .unwrap_or(defaults.aspect_ratio),
+        samples_per_pixel: section
+            .integer("samples_per_pixel")?
+            .unwrap_or(defaults.samples_per_pixel),
+        min_samples_per_pixel: section
+            .integer("min_samples_per_pixel")?
+            .unwrap_or(defaults.min_samples_per_pixel),
+        noise_threshold: section
+            .non_negative("noise_threshold")?
+            .unwrap_or(defaults.noise_threshold),
+        max_depth: section.integer("max_depth")?.unwrap_or(defaults.max_depth),
+        filter,
+        display,
+    };
+    image
+        .validate()
+        .map_err(|(key, message)| section.invalid(key, message))?;
+
+    Ok(image)
+}
+
+fn parse_camera(value: &Json) -> Result<CameraSettings, SceneError> {
+    let section = Section::new(
+        value,
+        "camera",
+        &[
+            "lookfrom",
+            "lookat",
+            "vup",
+            "vfov",
+            "aperture",
+            "focus_dist",
+            "shutter_open",
+            "shutter_close",
+        ],
+    )?;
+    let defaults = CameraSettings::default();
+    let camera = CameraSettings {
+        lookfrom: section.vec3("lookfrom")?.unwrap_or(defaults.lookfrom),
+        lookat: section.vec3("lookat")?.unwrap_or(defaults.lookat),
+        vup: section.vec3("vup")?.unwrap_or(defaults.vup),
+        vfov: section.positive("vfov")?.unwrap_or(defaults.vfov),
+        aperture: section
+            .non_negative("aperture")?
+            .unwrap_or(defaults.aperture),
+        focus_dist: section
+            .positive("focus_dist")?
+            .unwrap_or(defaults.focus_dist),
+        shutter_open: section
+            .number("shutter_open")?
+            .unwrap_or(defaults.shutter_open),
+        shutter_close: section
+            .number("shutter_close")?
+            .unwrap_or(defaults.shutter_close),
+    };
+    camera
+        .validate()
+        .map_err(|(key, message)| section.invalid(key, message))?;
+
+    Ok(camera)
+}
+
+fn parse_background(value: &Json) -> Result<Background, SceneError> {
+    let path = "background";
+    let kind = Section::new(value, path, &[])?.kind()?;
+    match kind {
+        "black" => {
+            Section::new(value, path, &["type"])?;
+            Ok(Background::Black)
+        }
+        "solid" => {
+            let section = Section::new(value, path, &["type", "color"])?;
+            Ok(Background::Solid(section.required_vec3("color")?))
+        }
+        "gradient" => {
+            let section = Section::new(value, path, &["type", "bottom", "top"])?;
+            Ok(Background::Gradient {
+                bottom: section.required_vec3("bottom")?,
+                top: section.required_vec3("top")?,
+            })
+        }
+        _ => Err(SceneError::Invalid {
+            key: format!("{}.type", path),
+            message: format!("unknown background type '{}'", kind),
+        }),
+    }
+}
+
+fn parse_fog(value: &Json) -> Result<Fog, SceneError> {
+    let section = Section::new(value, "fog", &["density", "albedo", "anisotropy"])?;
+    let defaults = Fog::default();
+    Ok(Fog {
+        density: section.non_negative("density")?.unwrap_or(defaults.density),
+        albedo: section.vec3("albedo")?.unwrap_or(defaults.albedo),
+        anisotropy: section.anisotropy()?,
+    })
+}
+
+fn parse_materials(value: &Json, dir: &Path) -> Result<HashMap<String, MaterialType>, SceneError> {
+    let members = match value {
+        Json::Object(members) => members,
+        _ => {
+            return Err(SceneError::Invalid {
+                key: "materials".to_string(),
+                message: format!("expected an object, found {}", value.type_name()),
+            })
+        }
+    };
+    let mut materials = HashMap::new();
+    for (name, value) in members.iter() {
+        let path = format!("materials.{}", name);
+        materials.insert(name.clone(), parse_material(value, &path, dir)?);
+    }
+
+    Ok(materials)
+}
+
+fn parse_material(value: &Json, path: &str, dir: &Path) -> Result<MaterialType, SceneError> {
+    let kind = Section::new(value, path, &[])?.kind()?;
+    match kind {
+        "lambertian" => {
+            let section = Section::new(value, path, &["type", "albedo"])?;
+            let albedo = section.required_texture("albedo", dir)?;
+            Ok(MaterialType::Lambertian(Lambertian::with_texture(albedo)))
+        }
+        "metal" => {
+            let section = Section::new(value, path, &["type", "albedo", "fuzz"])?;
+            let albedo = section.required_texture("albedo", dir)?;
+            let fuzz = section.non_negative("fuzz")?.unwrap_or(0.0);
+            Ok(MaterialType::Metal(Metal::with_texture(albedo, fuzz)))
+        }
+        "dielectric" => {
+            let section = Section::new(value, path, &["type", "ir"])?;
+            let ir = section.positive("ir")?.unwrap_or(1.5);
+            Ok(MaterialType::Dielectric(Dielectric::new(ir)))
+        }
+        "diffuse_light" => {
+            let section = Section::new(value, path, &["type", "emit"])?;
+            let emit = section.required_vec3("emit")?;
+            Ok(MaterialType::DiffuseLight(DiffuseLight::new(
+                emit.x(),
+                emit.y(),
+                emit.z(),
+            )))
+        }
+        _ => Err(SceneError::Invalid {
+            key: format!("{}.type", path),
+            message: format!("unknown material type '{}'", kind),
+        }),
+    }
+}
+
+/// Parses a texture, given either as a colour array or as a texture object.
+fn parse_texture(value: &Json, path: &str, dir: &Path) -> Result<TextureType, SceneError> {
+    if let Json::Array(_) = value {
+        return json_vec3(value, path).map(TextureType::from);
+    }
+    let kind = Section::new(value, path, &[])?.kind()?;
+    match kind {
+        "solid" => {
+            let section = Section::new(value, path, &["type", "color"])?;
+            Ok(TextureType::from(section.required_vec3("color")?))
+        }
+        "checker" => {
+            let section = Section::new(value, path, &["type", "scale", "even", "odd"])?;
+            Ok(TextureType::Checker(Checker::new(
+                section.positive("scale")?.unwrap_or(1.0),
+                section.required_texture("even", dir)?,
+                section.required_texture("odd", dir)?,
+            )))
+        }
+        "noise" => {
+            let section = Section::new(value, path, &["type", "kind", "scale", "seed"])?;
+            let kind = match section.get("kind") {
+                None => NoiseKind::Perlin,
+                Some(Json::String(kind)) => match kind.as_str() {
+                    "perlin" => NoiseKind::Perlin,
+                    "turbulence" => NoiseKind::Turbulence,
+                    "marble" => NoiseKind::Marble,
+                    _ => {
+                        return Err(
+                            section.invalid("kind", "expected one of: perlin, turbulence, marble")
+                        )
+                    }
+                },
+                Some(value) => return Err(section.type_error("kind", "a string", value)),
+            };
+            let scale = section.positive("scale")?.unwrap_or(1.0);
+            Ok(TextureType::Noise(Noise::new(kind, scale, section.seed()?)))
+        }
+        "image" => {
+            let section = Section::new(value, path, &["type", "file"])?;
+            let file = dir.join(section.required_string("file")?);
+            let texture = ImageTexture::load(&file).map_err(|error| SceneError::Io {
+                path: file.display().to_string(),
+                error,
+            })?;
+            Ok(TextureType::Image(texture))
+        }
+        _ => Err(SceneError::Invalid {
+            key: format!("{}.type", path),
+            message: format!("unknown texture type '{}'", kind),
+        }),
+    }
+}
+
+fn parse_object(
+    value: &Json,
+    path: &str,
+    materials: &HashMap<String, MaterialType>,
+    dir: &Path,
+    meshes: &mut MeshLoader,
+    world: &mut HitTableList<Box<dyn HitTable>>,
+    lights: &mut HitTableList<Arc<dyn HitTable>>,
+) -> Result<(), SceneError> {
+    let kind = Section::new(value, path, &[])?.kind()?;
+    match kind {
+        "sphere" => {
+            let section = Section::new(value, path, &["type", "center", "radius", "material"])?;
+            let center = section.required_vec3("center")?;
+            let radius = section.required_positive("radius")?;
+            let material = section.material(materials)?;
+            let emissive = matches!(material, MaterialType::DiffuseLight(_));
+            let sphere = Sphere::new(center.x(), center.y(), center.z(), radius, material);
+            add_shape(Arc::new(sphere), emissive, world, lights);
+        }
+        "moving_sphere" => {
+            let section = Section::new(
+                value,
+                path,
+                &[
+                    "type",
+                    "center0",
+                    "center1",
+                    "time0",
+                    "time1",
+                    "keyframes",
+                    "radius",
+                    "material",
+                ],
+            )?;
+            let keyframes = match section.get("keyframes") {
+                Some(keyframes) => {
+                    if section.get("center0").is_some() || section.get("center1").is_some() {
+                        return Err(
+                            section.invalid("keyframes", "cannot be combined with center0/center1")
+                        );
+                    }
+                    section.keyframes(keyframes)?
+                }
+                None => vec![
+                    (
+                        section.number("time0")?.unwrap_or(0.0),
+                        section.required_vec3("center0")?,
+                    ),
+                    (
+                        section.number("time1")?.unwrap_or(1.0),
+                        section.required_vec3("center1")?,
+                    ),
+                ],
+            };
+            let radius = section.required_positive("radius")?;
+            let material = section.material(materials)?;
+            // Never a sampled light; see `MovingSphere`.
+            world.add(Box::new(MovingSphere::keyframed(
+                keyframes, radius, material,
+            )));
+        }
+        "quad" => {
+            let section = Section::new(value, path, &["type", "q", "u", "v", "material"])?;
+            let q = section.required_vec3("q")?;
+            let u = section.required_vec3("u")?;
+            let v = section.required_vec3("v")?;
+            if Vec3::cross(u, v).near_zero() {
+                return Err(section.invalid("v", "must not be parallel to u"));
+            }
+            let material = section.material(materials)?;
+            let emissive = matches!(material, MaterialType::DiffuseLight(_));
+            add_shape(
+                Arc::new(Quad::new(q, u, v, material)),
+                emissive,
+                world,
+                lights,
+            );
+        }
+        "box" => {
+            let section = Section::new(value, path, &["type", "min", "max", "rotate", "material"])?;
+            let shape = parse_box(&section, section.material(materials)?)?;
+            world.add(shape);
+        }
+        "plane" => {
+            let section = Section::new(value, path, &["type", "point", "normal", "material"])?;
+            let point = section.required_vec3("point")?;
+            let normal = section.direction("normal")?;
+            let material = section.material(materials)?;
+            world.add(Box::new(Plane::new(point, normal, material)));
+        }
+        "disk" => {
+            let section = Section::new(
+                value,
+                path,
+                &[
+                    "type",
+                    "center",
+                    "normal",
+                    "radius",
+                    "inner_radius",
+                    "material",
+                ],
+            )?;
+            let center = section.required_vec3("center")?;
+            let normal = section.direction("normal")?;
+            let radius = section.required_positive("radius")?;
+            let inner_radius = section.non_negative("inner_radius")?.unwrap_or(0.0);
+            if inner_radius >= radius {
+                return Err(section.invalid("inner_radius", "must be less than radius"));
+            }
+            let material = section.material(materials)?;
+            let emissive = matches!(material, MaterialType::DiffuseLight(_));
+            add_shape(
+                Arc::new(Disk::annulus(
+                    center,
+                    normal,
+                    inner_radius,
+                    radius,
+                    material,
+                )),
+                emissive,
+                world,
+                lights,
+            );
+        }
+        "polygon" => {
+            let section = Section::new(value, path, &["type", "vertices", "material"])?;
+            let vertices = section.polygon("vertices")?;
+            let material = section.material(materials)?;
+            let emissive = matches!(material, MaterialType::DiffuseLight(_));
+            add_shape(
+                Arc::new(Polygon::new(vertices, material)),
+                emissive,
+                world,
+                lights,
+            );
+        }
+        "cylinder" | "cone" | "paraboloid" | "torus" => {
+            world.add(parse_quadric(value, path, kind, materials)?);
+        }
+        "csg" => world.add(parse_csg(value, path, materials)?),
+        "constant_medium" => {
+            let section = Section::new(
+                value,
+                path,
+                &["type", "boundary", "density", "albedo", "anisotropy"],
+            )?;
+            let boundary = match section.get("boundary") {
+                Some(boundary) => parse_boundary(boundary, &section.key("boundary"))?,
+                None => return Err(section.missing("boundary")),
+            };
+            let density = section.required_positive("density")?;
+            let albedo = section.required_texture("albedo", dir)?;
+            let phase = phase_function(albedo, section.anisotropy()?);
+            world.add(Box::new(ConstantMedium::with_phase_function(
+                boundary, density, phase,
+            )));
+        }
+        "volume" => {
+            let section = Section::new(
+                value,
+                path,
+                &[
+                    "type",
+                    "min",
+                    "max",
+                    "grid",
+                    "density",
+                    "albedo",
+                    "anisotropy",
+                ],
+            )?;
+            let (min, max) = section.corners()?;
+            let grid = match section.get("grid") {
+                Some(grid) => parse_grid(grid, &section.key("grid"), dir)?,
+                None => return Err(section.missing("grid")),
+            };
+            let density = section.required_positive("density")?;
+            let albedo = section.required_texture("albedo", dir)?;
+            let phase = phase_function(albedo, section.anisotropy()?);
+            world.add(Box::new(GridMedium::new(
+                Aabb::new(min, max),
+                grid,
+                density,
+                phase,
+            )));
+        }
+        "triangle" => {
+            let section = Section::new(value, path, &["type", "vertices", "material"])?;
+            let vertices = section.vertices("vertices")?;
+            let material = section.material(materials)?;
+            world.add(Box::new(Triangle::new(
+                vertices[0],
+                vertices[1],
+                vertices[2],
+                material,
+            )));
+        }
+        "mesh" => {
+            let section = Section::new(
+                value,
+                path,
+                &["type", "file", "scale", "rotate", "translate"],
+            )?;
+            let file = dir.join(section.required_string("file")?);
+            match section.transform()? {
+                Some(transform) => {
+                    if let Some(mesh) = meshes.load_shared(file)? {
+                        world.add(Box::new(Transformed::with_matrix(mesh, transform)));
+                    }
+                }
+                None => {
+                    for mesh in meshes.load(file)?.into_objects() {
+                        if !mesh.is_empty() {
+                            world.add(Box::new(mesh));
+                        }
+                    }
+                }
+            }
+        }
+        _ => {
+            return Err(SceneError::Invalid {
+                key: format!("{}.type", path),
+                message: format!("unknown object type '{}'", kind),
+            })
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a `cylinder`, `cone`, `paraboloid` or `torus`.
+fn parse_quadric(
+    value: &Json,
+    path: &str,
+    kind: &str,
+    materials: &HashMap<String, MaterialType>,
+) -> Result<Box<dyn HitTable>, SceneError> {
+    match kind {
+        "cylinder" | "cone" | "paraboloid" => {
+            let section = Section::new(
+                value,
+                path,
+                &[
+                    "type", "center", "radius", "height", "y_min", "y_max", "sweep", "capped",
+                    "material",
+                ],
+            )?;
+            let center = section.required_vec3("center")?;
+            let radius = section.required_positive("radius")?;
+            let height = section.required_positive("height")?;
+            let (y_min, y_max) = section.height_limits(0.0, height)?;
+            let sweep = section.sweep()?;
+            let capped = section.boolean("capped")?.unwrap_or(true);
+            let material = section.material(materials)?;
+            let shape: Box<dyn HitTable> = match kind {
+                "cylinder" => {
+                    let cylinder = Cylinder::new(center, radius, height, material)
+                        .with_height_limits(y_min, y_max)
+                        .with_sweep(sweep);
+                    Box::new(if capped {
+                        cylinder
+                    } else {
+                        cylinder.uncapped()
+                    })
+                }
+                "cone" => {
+                    let cone = Cone::new(center, radius, height, material)
+                        .with_height_limits(y_min, y_max)
+                        .with_sweep(sweep);
+                    Box::new(if capped { cone } else { cone.uncapped() })
+                }
+                _ => {
+                    let bowl = Paraboloid::new(center, radius, height, material)
+                        .with_height_limits(y_min, y_max)
+                        .with_sweep(sweep);
+                    Box::new(if capped { bowl } else { bowl.uncapped() })
+                }
+            };
+            Ok(shape)
+        }
+        "torus" => {
+            let section = Section::new(
+                value,
+                path,
+                &[
+                    "type",
+                    "center",
+                    "major_radius",
+                    "minor_radius",
+                    "y_min",
+                    "y_max",
+                    "sweep",
+                    "material",
+                ],
+            )?;
+            let center = section.required_vec3("center")?;
+            let major_radius = section.required_positive("major_radius")?;
+            let minor_radius = section.required_positive("minor_radius")?;
+            let (y_min, y_max) = section.height_limits(-minor_radius, minor_radius)?;
+            let sweep = section.sweep()?;
+            let material = section.material(materials)?;
+            Ok(Box::new(
+                Torus::new(center, major_radius, minor_radius, material)
+                    .with_height_limits(y_min, y_max)
+                    .with_sweep(sweep),
+            ))
+        }
+        _ => unreachable!("not a quadric: {}", kind),
+    }
+}
+
+/// Reads a `csg` combination of two solids `a` and `b`.
+fn parse_csg(
+    value: &Json,
+    path: &str,
+    materials: &HashMap<String, MaterialType>,
+) -> Result<Box<dyn HitTable>, SceneError> {
+    let section = Section::new(value, path, &["type", "operation", "a", "b"])?;
+    let operation = match section.required_string("operation")? {
+        "union" => CsgOperation::Union,
+        "intersection" => CsgOperation::Intersection,
+        "difference" => CsgOperation::Difference,
+        other => {
+            return Err(section.invalid(
+                "operation",
+                &format!(
+                    "unknown operation '{}', expected one of: union, intersection, difference",
+                    other
+                ),
+            ))
+        }
+    };
+    let operand = |key: &str| match section.get(key) {
+        Some(value) => parse_solid(value, &section.key(key), materials),
+        None => Err(section.missing(key)),
+    };
+    Ok(Box::new(Csg::new(operation, operand("a")?, operand("b")?)))
+}
+
+/// Reads a closed shape that can be combined by `csg`.
+fn parse_solid(
+    value: &Json,
+    path: &str,
+    materials: &HashMap<String, MaterialType>,
+) -> Result<Box<dyn HitTable>, SceneError> {
+    let kind = Section::new(value, path, &[])?.kind()?;
+    match kind {
+        "sphere" => {
+            let section = Section::new(value, path, &["type", "center", "radius", "material"])?;
+            let center = section.required_vec3("center")?;
+            let radius = section.required_positive("radius")?;
+            let material = section.material(materials)?;
+            Ok(Box::new(Sphere::new(
+                center.x(),
+                center.y(),
+                center.z(),
+                radius,
+                material,
+            )))
+        }
+        "box" => {
+            let section = Section::new(value, path, &["type", "min", "max", "rotate", "material"])?;
+            parse_box(&section, section.material(materials)?)
+        }
+        "cylinder" | "cone" | "paraboloid" | "torus" => parse_quadric(value, path, kind, materials),
+        "csg" => parse_csg(value, path, materials),
+        _ => Err(SceneError::Invalid {
+            key: format!("{}.type", path),
+            message: format!("unknown solid type '{}'", kind),
+        }),
+    }
+}
+
+/// Reads the densities of a `volume`, either from a raw file of
+/// little-endian floats or generated from noise.
+fn parse_grid(value: &Json, path: &str, dir: &Path) -> Result<DensityGrid, SceneError> {
+    let kind = Section::new(value, path, &[])?.kind()?;
+    match kind {
+        "raw" => {
+            let section = Section::new(value, path, &["type", "file", "resolution"])?;
+            let file = dir.join(section.required_string("file")?);
+            let resolution = section.resolution()?;
+            DensityGrid::load(&file, resolution).map_err(|error| SceneError::Io {
+                path: file.display().to_string(),
+                error,
+            })
+        }
+        "noise" => {
+            let section = Section::new(value, path, &["type", "resolution", "scale", "seed"])?;
+            let resolution = section.resolution()?;
+            let scale = section.positive("scale")?.unwrap_or(4.0);
+            Ok(DensityGrid::from_noise(resolution, scale, section.seed()?))
+        }
+        _ => Err(SceneError::Invalid {
+            key: format!("{}.type", path),
+            message: format!("unknown grid type '{}'", kind),
+        }),
+    }
+}
+
+/// Reads the closed shape enclosing a medium, which needs no material.
+fn parse_boundary(value: &Json, path: &str) -> Result<Box<dyn HitTable>, SceneError> {
+    let kind = Section::new(value, path, &[])?.kind()?;
+    match kind {
+        "sphere" => {
+            let section = Section::new(value, path, &["type", "center", "radius"])?;
+            let center = section.required_vec3("center")?;
+            let radius = section.required_positive("radius")?;
+            Ok(Box::new(Sphere::new(
+                center.x(),
+                center.y(),
+                center.z(),
+                radius,
+                MaterialType::None,
+            )))
+        }
+        "box" => {
+            let section = Section::new(value, path, &["type", "min", "max", "rotate"])?;
+            parse_box(&section, MaterialType::None)
+        }
+        _ => Err(SceneError::Invalid {
+            key: format!("{}.type", path),
+            message: format!("unknown boundary type '{}'", kind),
+        }),
+    }
+}
+
+/// Reads a box between corners `min` and `max`, turned about its centre by
+/// the optional `rotate` degrees around x, then y, then z.
+fn parse_box(section: &Section, material: MaterialType) -> Result<Box<dyn HitTable>, SceneError> {
+    let (min, max) = section.corners()?;
+    Ok(match section.vec3("rotate")? {
+        Some(degrees) => {
+            let center = 0.5 * (min + max);
+            Box::new(
+                Transformed::new(AxisAlignedBox::new(min - center, max - center, material))
+                    .then(Mat4::rotation_xyz(degrees))
+                    .translate(center),
+            )
+        }
+        None => Box::new(AxisAlignedBox::new(min, max, material)),
+    })
+}
+
+/// Loads the OBJ files of a scene, gathering the warnings they raise.
+#[derive(Default)]
+struct MeshLoader {
+    /// Meshes placed with a transform, by file, so that every instance of a
+    /// file shares one copy of its triangles. `None` marks a file with none.
+    shared: HashMap<PathBuf, Option<Arc<dyn HitTable>>>,
+    warnings: Vec<String>,
+}
+
+impl MeshLoader {
+    fn load(&mut self, file: PathBuf) -> Result<HitTableList<TriangleMesh>, SceneError> {
+        let model = obj::load(file)?;
+        self.warnings.extend(model.warnings);
+        Ok(model.meshes)
+    }
+
+    /// Loads the meshes of an OBJ file under one BVH, or reuses them if the
+    /// file was loaded before.
+    fn load_shared(&mut self, file: PathBuf) -> Result<Option<Arc<dyn HitTable>>, SceneError> {
+        if let Some(mesh) = self.shared.get(&file) {
+            return Ok(mesh.clone());
+        }
+        let objects: Vec<Arc<dyn HitTable>> = self
+            .load(file.clone())?
+            .into_objects()
+            .into_iter()
+            .filter(|mesh| !mesh.is_empty())
+            .map(|mesh| Arc::new(mesh) as Arc<dyn HitTable>)
+            .collect();
+        let mesh = if objects.is_empty() {
+            None
+        } else {
+            Some(Arc::new(BvhNode::from_objects(objects)) as Arc<dyn HitTable>)
+        };
+        self.shared.insert(file, mesh.clone());
+        Ok(mesh)
+    }
+}
+
+/// Adds a shape to the world, and to the sampled lights when it is emissive.
+fn add_shape(
+    shape: Arc<dyn HitTable>,
+    emissive: bool,
+    world: &mut HitTableList<Box<dyn HitTable>>,
+    lights: &mut HitTableList<Arc<dyn HitTable>>,
+) {
+    if emissive {
+        lights.add(shape.clone());
+    }
+    world.add(Box::new(shape));
+}
+
+/// Reads a `[x, y, z]` array; `key` is the full path used in errors.
+fn json_vec3(value: &Json, key: &str) -> Result<Vec3, SceneError> {
+    let invalid = |found: &str| SceneError::Invalid {
+        key: key.to_string(),
+        message: format!("expected an array of 3 numbers, found {}", found),
+    };
+    match value {
+        Json::Array(items) if items.len() == 3 => {
+            let mut v = [0.0; 3];
+            for (slot, item) in v.iter_mut().zip(items.iter()) {
+                match item {
+                    Json::Number(n) => *slot = *n,
+                    _ => return Err(invalid(item.type_name())),
+                }
+            }
+            Ok(Vec3::new(v[0], v[1], v[2]))
+        }
+        Json::Array(items) => Err(invalid(&format!("{} items", items.len()))),
+        _ => Err(invalid(value.type_name())),
+    }
+}
+
+/// A JSON object being validated, remembering its path for error messages.
+struct Section<'a> {
+    path: String,
+    members: &'a [(String, Json)],
+}
+
+impl<'a> Section<'a> {
+    /// Checks that `value` is an object whose keys are all in `allowed`. An
+    /// empty `allowed` list skips the key check.
+    fn new(value: &'a Json, path: &str, allowed: &[&str]) -> Result<Section<'a>, SceneError> {
+        let members = match value {
+            Json::Object(members) => members,
+            _ => {
+                return Err(SceneError::Invalid {
+                    key: path.to_string(),
+                    message: format!("expected an object, found {}", value.type_name()),
+                })
+            }
+        };
+        let section = Section {
+            path: path.to_string(),
+            members,
+        };
+        if !allowed.is_empty() {
+            if let Some((key, _)) = members.iter().find(|(k, _)| !allowed.contains(&k.as_str())) {
+                return Err(section.invalid(
+                    key,
+                    &format!("unknown key, expected one of: {}", allowed.join(", ")),
+                ));
+            }
+        }
+
+        Ok(section)
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}.{}", self.path, key)
+    }
+
+    fn invalid(&self, key: &str, message: &str) -> SceneError {
+        SceneError::Invalid {
+            key: self.key(key),
+            message: message.to_string(),
+        }
+    }
+
+    fn missing(&self, key: &str) -> SceneError {
+        self.invalid(key, "missing required key")
+    }
+
+    fn type_error(&self, key: &str, expected: &str, found: &Json) -> SceneError {
+        self.invalid(
+            key,
+            &format!("expected {}, found {}", expected, found.type_name()),
+        )
+    }
+
+    fn get(&self, key: &str) -> Option<&'a Json> {
+        self.members.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    fn kind(&self) -> Result<&'a str, SceneError> {
+        self.required_string("type")
+    }
+
+    fn number(&self, key: &str) -> Result<Option<f64>, SceneError> {
+        match self.get(key) {
+            Some(Json::Number(n)) => Ok(Some(*n)),
+            Some(value) => Err(self.type_error(key, "a number", value)),
+            None => Ok(None),
+        }
+    }
+
+    fn positive(&self, key: &str) -> Result<Option<f64>, SceneError> {
+        match self.number(key)? {
+            Some(n) if n <= 0.0 => Err(self.invalid(key, "must be greater than zero")),
+            n => Ok(n),
+        }
+    }
+
+    fn non_negative(&self, key: &str) -> Result<Option<f64>, SceneError> {
+        match self.number(key)? {
+            Some(n) if n < 0.0 => Err(self.invalid(key, "must not be negative")),
+            n => Ok(n),
+        }
+    }
+
+    fn required_positive(&self, key: &str) -> Result<f64, SceneError> {
+        self.positive(key)?.ok_or_else(|| self.missing(key))
+    }
+
+    fn integer(&self, key: &str) -> Result<Option<u32>, SceneError> {
+        match self.get(key) {
+            Some(Json::Number(n)) if n.fract() == 0.0 && *n >= 1.0 && *n <= u32::MAX as f64 => {
+                Ok(Some(*n as u32))
+            }
+            Some(value) => Err(self.type_error(key, "a positive integer", value)),
+            None => Ok(None),
+        }
+    }
+
+    fn string(&self, key: &str) -> Result<Option<&'a str>, SceneError> {
+        match self.get(key) {
+            Some(Json::String(s)) => Ok(Some(s)),
+            Some(value) => Err(self.type_error(key, "a string", value)),
+            None => Ok(None),
+        }
+    }
+
+    fn required_string(&self, key: &str) -> Result<&'a str, SceneError> {
+        self.string(key)?.ok_or_else(|| self.missing(key))
+    }
+
+    fn vec3(&self, key: &str) -> Result<Option<Vec3>, SceneError> {
+        match self.get(key) {
+            Some(value) => self.to_vec3(key, value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn required_vec3(&self, key: &str) -> Result<Vec3, SceneError> {
+        self.vec3(key)?.ok_or_else(|| self.missing(key))
+    }
+
+    fn boolean(&self, key: &str) -> Result<Option<bool>, SceneError> {
+        match self.get(key) {
+            Some(Json::Bool(b)) => Ok(Some(*b)),
+            Some(value) => Err(self.type_error(key, "a boolean", value)),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the optional `y_min` and `y_max` cutting a shape that spans
+    /// heights `low` to `high`.
+    fn height_limits(&self, low: f64, high: f64) -> Result<(f64, f64), SceneError> {
+        let y_min = self.number("y_min")?.map_or(low, |y| y.max(low));
+        let y_max = self.number("y_max")?.map_or(high, |y| y.min(high));
+        if y_min >= y_max {
+            return Err(self.invalid("y_max", "leaves nothing of the shape above y_min"));
+        }
+        Ok((y_min, y_max))
+    }
+
+    /// Reads the optional `sweep` around the axis, a full turn by default.
+    fn sweep(&self) -> Result<f64, SceneError> {
+        match self.number("sweep")? {
+            Some(degrees) if degrees <= 0.0 || degrees > 360.0 => {
+                Err(self.invalid("sweep", "must be between 0 and 360 degrees"))
+            }
+            degrees => Ok(degrees.unwrap_or(360.0)),
+        }
+    }
+
+    /// Reads the optional `scale`, `rotate` and `translate` placing an
+    /// object, applied in that order, or `None` when there are none.
+    /// `scale` is a number or a factor per axis and `rotate` is in degrees
+    /// around x, then y, then z.
+    fn transform(&self) -> Result<Option<Mat4>, SceneError> {
+        let scale = match self.get("scale") {
+            None => None,
+            Some(Json::Number(n)) => Some(Vec3::new(*n, *n, *n)),
+            Some(value) => Some(self.to_vec3("scale", value)?),
+        };
+        if scale.is_some_and(|s| (0..3).any(|axis| s[axis] == 0.0)) {
+            return Err(self.invalid("scale", "must not be zero"));
+        }
+        let rotate = self.vec3("rotate")?;
+        let translate = self.vec3("translate")?;
+        if scale.is_none() && rotate.is_none() && translate.is_none() {
+            return Ok(None);
+        }
+        let transform = Mat4::translation(translate.unwrap_or_default())
+            * Mat4::rotation_xyz(rotate.unwrap_or_default())
+            * Mat4::scaling(scale.unwrap_or(Vec3::new(1.0, 1.0, 1.0)));
+        // Rotations and translations always invert, so only a scale too
+        // small to undo can make this fail.
+        if transform.inverse().is_none() {
+            return Err(self.invalid("scale", "must be invertible"));
+        }
+        Ok(Some(transform))
+    }
+
+    /// Reads the optional noise `seed`, zero by default.
+    fn seed(&self) -> Result<u64, SceneError> {
+        match self.get("seed") {
+            None => Ok(0),
+            Some(Json::Number(n)) if n.fract() == 0.0 && *n >= 0.0 => Ok(*n as u64),
+            Some(value) => Err(self.type_error("seed", "a non-negative integer", value)),
+        }
+    }
+
+    /// Reads the optional Henyey-Greenstein `anisotropy` of a medium.
+    fn anisotropy(&self) -> Result<f64, SceneError> {
+        match self.number("anisotropy")? {
+            Some(g) if g <= -1.0 || g >= 1.0 => {
+                Err(self.invalid("anisotropy", "must be between -1 and 1"))
+            }
+            g => Ok(g.unwrap_or(0.0)),
+        }
+    }
+
+    /// Reads the number of voxels along each axis of a density grid.
+    fn resolution(&self) -> Result<[usize; 3], SceneError> {
+        let v = self.required_vec3("resolution")?;
+        let mut resolution = [0; 3];
+        for (axis, n) in resolution.iter_mut().enumerate() {
+            if v[axis].fract() != 0.0 || v[axis] < 1.0 {
+                return Err(self.invalid("resolution", "expected positive integers"));
+            }
+            *n = v[axis] as usize;
+        }
+        Ok(resolution)
+    }
+
+    fn required_texture(&self, key: &str, dir: &Path) -> Result<TextureType, SceneError> {
+        match self.get(key) {
+            Some(value) => parse_texture(value, &self.key(key), dir),
+            None => Err(self.missing(key)),
+        }
+    }
+
+    fn to_vec3(&self, key: &str, value: &Json) -> Result<Vec3, SceneError> {
+        json_vec3(value, &self.key(key))
+    }
+
+    /// Reads a non-empty array of `{ "time": t, "center": [x, y, z] }`.
+    fn keyframes(&self, value: &Json) -> Result<Vec<(f64, Point3)>, SceneError> {
+        let items = match value {
+            Json::Array(items) if !items.is_empty() => items,
+            Json::Array(_) => return Err(self.invalid("keyframes", "must not be empty")),
+            _ => return Err(self.type_error("keyframes", "an array", value)),
+        };
+        items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let keyframe = Section::new(
+                    item,
+                    &format!("{}[{}]", self.key("keyframes"), i),
+                    &["time", "center"],
+                )?;
+                let time = keyframe
+                    .number("time")?
+                    .ok_or_else(|| keyframe.missing("time"))?;
+                Ok((time, keyframe.required_vec3("center")?))
+            })
+            .collect()
+    }
+
+    /// Reads the `min` and `max` corners of a box.
+    fn corners(&self) -> Result<(Point3, Point3), SceneError> {
+        let (min, max) = (self.required_vec3("min")?, self.required_vec3("max")?);
+        if (0..3).any(|axis| min[axis] >= max[axis]) {
+            return Err(self.invalid("max", "must be greater than min on every axis"));
+        }
+        Ok((min, max))
+    }
+
+    /// Reads a non-zero vector giving a direction.
+    fn direction(&self, key: &str) -> Result<Vec3, SceneError> {
+        let v = self.required_vec3(key)?;
+        if v.near_zero() {
+            return Err(self.invalid(key, "must not be zero"));
+        }
+        Ok(v)
+    }
+
+    /// Reads the corners of a flat convex polygon, in order around it.
+    fn polygon(&self, key: &str) -> Result<Vec<Point3>, SceneError> {
+        let points = match self.get(key) {
+            Some(Json::Array(items)) if items.len() >= 3 => items
+                .iter()
+                .map(|item| self.to_vec3(key, item))
+                .collect::<Result<Vec<_>, _>>()?,
+            Some(_) => return Err(self.invalid(key, "expected an array of at least 3 points")),
+            None => return Err(self.missing(key)),
+        };
+
+        let p0 = points[0];
+        let (mut normal, mut size) = (Vec3::default(), 0.0f64);
+        for pair in points[1..].windows(2) {
+            let cross = Vec3::cross(pair[0] - p0, pair[1] - p0);
+            if Vec3::dot(cross, normal) < 0.0 {
+                return Err(self.invalid(key, "must be convex"));
+            }
+            normal += cross;
+            size = size.max((pair[0] - p0).length());
+        }
+        if normal.near_zero() {
+            return Err(self.invalid(key, "must enclose an area"));
+        }
+        let normal = normal.unit_vector();
+        if points
+            .iter()
+            .any(|&p| Vec3::dot(p - p0, normal).abs() > 1e-6 * size)
+        {
+            return Err(self.invalid(key, "must lie in a plane"));
+        }
+        Ok(points)
+    }
+
+    fn vertices(&self, key: &str) -> Result<[Point3; 3], SceneError> {
+        match self.get(key) {
+            Some(Json::Array(items)) if items.len() == 3 => Ok([
+                self.to_vec3(key, &items[0])?,
+                self.to_vec3(key, &items[1])?,
+                self.to_vec3(key, &items[2])?,
+            ]),
+            Some(_) => Err(self.invalid(key, "expected an array of 3 points")),
+            None => Err(self.missing(key)),
+        }
+    }
+
+    fn material(
+        &self,
+        materials: &HashMap<String, MaterialType>,
+    ) -> Result<MaterialType, SceneError> {
+        let name = self.required_string("material")?;
+        match materials.get(name) {
+            Some(material) => Ok(material.clone()),
+            None => Err(self.invalid("material", &format!("unknown material '{}'", name))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ray::Ray;
+    use crate::core::HitRecord;
+
+    fn parse(source: &str) -> Result<Scene, SceneError> {
+        Scene::parse(source, "test.json", Path::new(""))
+    }
+
+    /// Parses a scene holding `objects`, which may use the materials "grey"
+    /// and "lamp".
+    fn parse_objects(objects: &str) -> Result<Scene, SceneError> {
+        parse(&format!(
+            r#"{{
+                "materials": {{
+                    "grey": {{ "type": "lambertian", "albedo": [0.5, 0.5, 0.5] }},
+                    "lamp": {{ "type": "diffuse_light", "emit": [4, 4, 4] }}
+                }},
+                "objects": [{}]
+            }}"#,
+            objects
+        ))
+    }
+
+    fn objects_error(objects: &str) -> String {
+        parse_objects(objects).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn parse_scene_test() {
+        let scene = parse(
+            r#"{
+                "image": { "width": 400, "samples_per_pixel": 10, "tone_map": "aces" },
+                "camera": { "lookfrom": [0, 0, 5], "vfov": 40 },
+                "materials": { "red": { "type": "lambertian", "albedo": [0.8, 0.1, 0.1] } },
+                "objects": [{ "type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "red" }]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(scene.image.width, 400);
+        assert_eq!(scene.image.height(), 266);
+        assert_eq!(scene.image.max_depth, 50);
+        assert_eq!(scene.image.display.tone_mapper, ToneMapper::Aces);
+        assert_eq!(scene.camera.lookfrom, Point3::new(0.0, 0.0, 5.0));
+        assert_eq!(scene.camera.vfov, 40.0);
+        assert_eq!(scene.world.len(), 1);
+    }
+
+    #[test]
+    fn flat_shapes_test() {
+        let scene = parse_objects(
+            r#"{ "type": "plane", "point": [0, -1, 0], "normal": [0, 1, 0], "material": "grey" },
+               { "type": "disk", "center": [0, 3, 0], "normal": [0, -1, 0], "radius": 1,
+                 "inner_radius": 0.25, "material": "lamp" },
+               { "type": "polygon", "vertices": [[2, 0, 0], [3, 0, 0], [3, 1, 0], [2, 1, 0]],
+                 "material": "grey" }"#,
+        )
+        .unwrap();
+        assert_eq!(scene.world.len(), 3);
+        assert_eq!(scene.lights.len(), 1);
+    }
+
+    #[test]
+    fn disk_inner_radius_error_test() {
+        assert_eq!(
+            objects_error(
+                r#"{ "type": "disk", "center": [0, 0, 0], "normal": [0, 1, 0], "radius": 1,
+                     "inner_radius": 1, "material": "grey" }"#
+            ),
+            "objects[0].inner_radius: must be less than radius"
+        );
+    }
+
+    #[test]
+    fn non_planar_polygon_error_test() {
+        assert_eq!(
+            objects_error(
+                r#"{ "type": "polygon", "vertices": [[0, 0, 0], [1, 0, 0], [1, 1, 0], [0, 1, 1]],
+                     "material": "grey" }"#
+            ),
+            "objects[0].vertices: must lie in a plane"
+        );
+    }
+
+    #[test]
+    fn boxes_test() {
+        let scene = parse_objects(
+            r#"{ "type": "box", "min": [-1, 0, -1], "max": [1, 1, 1], "material": "grey" },
+               { "type": "box", "min": [2, 0, 0], "max": [3, 1, 1], "rotate": [10, 20, 30],
+                 "material": "grey" },
+               { "type": "constant_medium", "density": 0.5, "albedo": [1, 1, 1],
+                 "boundary": { "type": "box", "min": [-1, 2, -1], "max": [1, 3, 1],
+                               "rotate": [0, 45, 0] } }"#,
+        )
+        .unwrap();
+        assert_eq!(scene.world.len(), 3);
+    }
+
+    #[test]
+    fn box_corners_error_test() {
+        assert_eq!(
+            objects_error(
+                r#"{ "type": "box", "min": [0, 0, 0], "max": [1, 0, 1], "material": "grey" }"#
+            ),
+            "objects[0].max: must be greater than min on every axis"
+        );
+    }
+
+    #[test]
+    fn box_rotate_error_test() {
+        assert_eq!(
+            objects_error(
+                r#"{ "type": "box", "min": [0, 0, 0], "max": [1, 1, 1], "rotate": 45,
+                     "material": "grey" }"#
+            ),
+            "objects[0].rotate: expected an array of 3 numbers, found number"
+        );
+    }
+
+    #[test]
+    fn quadrics_test() {
+        let scene = parse_objects(
+            r#"{ "type": "cylinder", "center": [0, 0, 0], "radius": 1, "height": 2,
+                 "y_min": 0.5, "sweep": 270, "capped": false, "material": "grey" },
+               { "type": "cone", "center": [3, 0, 0], "radius": 1, "height": 2, "y_max": 1.5,
+                 "sweep": 180, "material": "grey" },
+               { "type": "paraboloid", "center": [6, 0, 0], "radius": 1, "height": 1,
+                 "y_min": 0.25, "y_max": 0.75, "material": "grey" },
+               { "type": "torus", "center": [9, 0, 0], "major_radius": 1, "minor_radius": 0.25,
+                 "y_min": 0, "sweep": 90, "material": "grey" }"#,
+        )
+        .unwrap();
+        assert_eq!(scene.world.len(), 4);
+    }
+
+    #[test]
+    fn quadric_sweep_error_test() {
+        assert_eq!(
+            objects_error(
+                r#"{ "type": "cone", "center": [0, 0, 0], "radius": 1, "height": 2, "sweep": 400,
+                     "material": "grey" }"#
+            ),
+            "objects[0].sweep: must be between 0 and 360 degrees"
+        );
+    }
+
+    #[test]
+    fn quadric_height_limits_error_test() {
+        assert_eq!(
+            objects_error(
+                r#"{ "type": "torus", "center": [0, 0, 0], "major_radius": 1, "minor_radius": 0.25,
+                     "y_min": 0.25, "material": "grey" }"#
+            ),
+            "objects[0].y_max: leaves nothing of the shape above y_min"
+        );
+    }
+
+    #[test]
+    fn quadric_capped_error_test() {
+        assert_eq!(
+            objects_error(
+                r#"{ "type": "cylinder", "center": [0, 0, 0], "radius": 1, "height": 2,
+                     "capped": 1, "material": "grey" }"#
+            ),
+            "objects[0].capped: expected a boolean, found number"
+        );
+    }
+
+    /// A directory under the system temp dir, removed with its contents on
+    /// drop so a failing assertion does not leave files behind.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn mesh_test() {
+        let dir = TempDir::new("scene_mesh");
+        fs::write(
+            dir.0.join("tri.obj"),
+            "vp 0.5\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n",
+        )
+        .unwrap();
+        let scene = Scene::parse(
+            r#"{ "objects": [{ "type": "mesh", "file": "tri.obj", "scale": 2,
+                               "rotate": [0, 180, 0], "translate": [5, 0, 0] },
+                             { "type": "mesh", "file": "tri.obj", "translate": [0, 0, -10] }] }"#,
+            "test.json",
+            &dir.0,
+        )
+        .unwrap();
+
+        // Doubled, turned to span x from 3 to 5, and instanced twice.
+        assert_eq!(scene.world.len(), 2);
+        assert_eq!(scene.warnings.len(), 1);
+        assert!(scene.warnings[0].ends_with("tri.obj:1: ignoring unsupported statement 'vp'"));
+        let mut rec = HitRecord::default();
+        let toward = Ray::new(Point3::new(4.5, 0.5, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(scene.world.hit(&toward, 0.001, 10.0, &mut rec));
+        assert!((rec.t - 5.0).abs() < 1e-9);
+        let beside = Ray::new(Point3::new(5.5, 0.5, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(!scene.world.hit(&beside, 0.001, 10.0, &mut rec));
+    }
+
+    #[test]
+    fn mesh_scale_zero_error_test() {
+        let error =
+            parse(r#"{ "objects": [{ "type": "mesh", "file": "tri.obj", "scale": [1, 0, 1] }] }"#)
+                .err()
+                .unwrap();
+        assert_eq!(error.to_string(), "objects[0].scale: must not be zero");
+    }
+
+    #[test]
+    fn csg_test() {
+        let scene = parse_objects(
+            r#"{ "type": "csg", "operation": "difference",
+                 "a": { "type": "box", "min": [-1, -1, -1], "max": [1, 1, 1], "material": "grey" },
+                 "b": { "type": "csg", "operation": "union",
+                        "a": { "type": "sphere", "center": [0, 0, 0], "radius": 0.5,
+                               "material": "grey" },
+                        "b": { "type": "cylinder", "center": [0, -2, 0], "radius": 0.25,
+                               "height": 4, "material": "grey" } } }"#,
+        )
+        .unwrap();
+        assert_eq!(scene.world.len(), 1);
+    }
+
+    #[test]
+    fn csg_solid_type_error_test() {
+        assert_eq!(
+            objects_error(
+                r#"{ "type": "csg", "operation": "union",
+                     "a": { "type": "plane", "point": [0, 0, 0], "normal": [0, 1, 0],
+                            "material": "grey" },
+                     "b": { "type": "sphere", "center": [0, 0, 0], "radius": 1,
+                            "material": "grey" } }"#
+            ),
+            "objects[0].a.type: unknown solid type 'plane'"
+        );
+    }
+
+    #[test]
+    fn volume_test() {
+        let scene = parse_objects(
+            r#"{ "type": "volume", "min": [3, 0, 0], "max": [4, 1, 1], "density": 2,
+                 "albedo": [0.8, 0.8, 0.8], "anisotropy": 0.3,
+                 "grid": { "type": "noise", "resolution": [4, 4, 4], "seed": 3 } }"#,
+        )
+        .unwrap();
+        assert_eq!(scene.world.len(), 1);
+    }
+
+    #[test]
+    fn volume_anisotropy_error_test() {
+        assert_eq!(
+            objects_error(
+                r#"{ "type": "volume", "min": [0, 0, 0], "max": [1, 1, 1], "density": 2,
+                     "albedo": [1, 1, 1], "anisotropy": 1,
+                     "grid": { "type": "noise", "resolution": [4, 4, 4] } }"#
+            ),
+            "objects[0].anisotropy: must be between -1 and 1"
+        );
+    }
+
+    #[test]
+    fn mesh_scale_must_be_invertible_test() {
+        let error =
+            parse(r#"{ "objects": [{ "type": "mesh", "file": "tri.obj", "scale": 1e-13 }] }"#)
+                .err()
+                .unwrap();
+        assert_eq!(error.to_string(), "objects[0].scale: must be invertible");
+    }
+
+    #[test]
+    fn rotated_box_test() {
+        // A unit cube turned 45 degrees about y shows an edge to the z axis.
+        let scene = parse(
+            r#"{
+                "materials": { "grey": { "type": "lambertian", "albedo": [0.5, 0.5, 0.5] } },
+                "objects": [{ "type": "box", "min": [-0.5, -0.5, -0.5], "max": [0.5, 0.5, 0.5],
+                              "rotate": [0, 45, 0], "material": "grey" }]
+            }"#,
+        )
+        .unwrap();
+        let half_diagonal = 0.5f64.sqrt();
+        let mut rec = HitRecord::default();
+
+        let toward = Ray::new(Point3::new(0.1, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(scene.world.hit(&toward, 0.001, f64::INFINITY, &mut rec));
+        assert!((rec.t - (5.0 - half_diagonal + 0.1)).abs() < 1e-9);
+        assert!((rec.normal - Vec3::new(half_diagonal, 0.0, -half_diagonal)).near_zero());
+
+        let beside = Ray::new(Point3::new(0.75, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(!scene.world.hit(&beside, 0.001, f64::INFINITY, &mut rec));
+    }
+
+    #[test]
+    fn validation_errors_test() {
+        let error = |source: &str| parse(source).err().unwrap().to_string();
+
+        assert_eq!(
+            error(r#"{ "objects": [], "lights": [] }"#),
+            "scene.lights: unknown key, expected one of: image, camera, background, fog, materials, objects"
+        );
+        assert_eq!(
+            error(r#"{ "image": { "width": "wide" }, "objects": [] }"#),
+            "image.width: expected a positive integer, found string"
+        );
+        assert_eq!(
+            error(r#"{ "image": { "tone_map": "filmic" }, "objects": [] }"#),
+            "image.tone_map: unknown tone mapper 'filmic', expected one of: clamp, reinhard, reinhard_extended, aces, hable"
+        );
+        assert_eq!(
+            error(
+                r#"{ "objects": [{ "type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "gold" }] }"#
+            ),
+            "objects[0].material: unknown material 'gold'"
+        );
+        assert_eq!(
+            error(
+                r#"{ "objects": [{ "type": "csg", "operation": "xor", "a": {}, "b": {} }] }"#
+            ),
+            "objects[0].operation: unknown operation 'xor', expected one of: union, intersection, difference"
+        );
+        assert_eq!(
+            error("{ \"objects\": [}"),
+            "test.json:1:15: unexpected character '}'"
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use crate::core::Texture;
+use crate::textures::TextureType;
+use crate::vec3::{Color, Point3};
+
+/// Solid 3D checker pattern alternating between two textures in cubes of
+/// side `scale`.
+#[derive(Clone)]
+pub struct Checker {
+    inv_scale: f64,
+    even: Arc<TextureType>,
+    odd: Arc<TextureType>,
+}
+
+impl Checker {
+    pub fn new(scale: f64, even: TextureType, odd: TextureType) -> Checker {
+        Checker {
+            inv_scale: 1.0 / scale,
+            even: Arc::new(even),
+            odd: Arc::new(odd),
+        }
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        let x = (self.inv_scale * p.x()).floor() as i64;
+        let y = (self.inv_scale * p.y()).floor() as i64;
+        let z = (self.inv_scale * p.z()).floor() as i64;
+
+        if (x + y + z) % 2 == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checker(scale: f64) -> Checker {
+        Checker::new(
+            scale,
+            TextureType::from(Color::new(1.0, 1.0, 1.0)),
+            TextureType::from(Color::new(0.0, 0.0, 0.0)),
+        )
+    }
+
+    #[test]
+    fn parity_test() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let unit = checker(1.0);
+        let value = |x, y, z| unit.value(0.0, 0.0, &Point3::new(x, y, z));
+
+        assert_eq!(value(0.5, 0.5, 0.5), white);
+        assert_eq!(value(1.5, 0.5, 0.5), black);
+        assert_eq!(value(1.5, 1.5, 0.5), white);
+        assert_eq!(value(1.5, 1.5, 1.5), black);
+        // Cells below zero keep alternating.
+        assert_eq!(value(-0.5, 0.5, 0.5), black);
+        assert_eq!(value(-0.5, -0.5, 0.5), white);
+        assert_eq!(value(-1.5, 0.5, 0.5), white);
+
+        let wide = checker(2.0);
+        assert_eq!(wide.value(0.0, 0.0, &Point3::new(1.5, 0.5, 0.5)), white);
+        assert_eq!(wide.value(0.0, 0.0, &Point3::new(2.5, 0.5, 0.5)), black);
+    }
+}
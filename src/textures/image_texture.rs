@@ -0,0 +1,92 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::core::{clamp, Texture};
+use crate::ppm;
+use crate::vec3::{Color, Point3};
+
+/// Texture sampled from an image with (0, 0) at the bottom-left corner.
+#[derive(Clone)]
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    data: Arc<Vec<Color>>,
+}
+
+impl ImageTexture {
+    /// `data` holds linear colours in rows from top to bottom.
+    pub fn new(width: usize, height: usize, data: Vec<Color>) -> ImageTexture {
+        assert_eq!(data.len(), width * height, "image data size mismatch");
+        ImageTexture {
+            width,
+            height,
+            data: Arc::new(data),
+        }
+    }
+
+    /// Loads a PPM image, converting its gamma encoded values to linear.
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<ImageTexture> {
+        let (width, height, rgb) = ppm::load(path)?;
+        let data = rgb
+            .iter()
+            .map(|[r, g, b]| {
+                let decode = |c: u8| {
+                    let c = c as f64 / 255.0;
+                    c * c
+                };
+                Color::new(decode(*r), decode(*g), decode(*b))
+            })
+            .collect();
+        Ok(ImageTexture::new(width, height, data))
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Point3) -> Color {
+        if self.data.is_empty() {
+            return Color::new(0.0, 1.0, 1.0);
+        }
+        let u = clamp(u, 0.0, 1.0);
+        let v = 1.0 - clamp(v, 0.0, 1.0);
+        let i = ((u * self.width as f64) as usize).min(self.width - 1);
+        let j = ((v * self.height as f64) as usize).min(self.height - 1);
+
+        self.data[j * self.width + i]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn orientation_and_clamping_test() {
+        // Red and green on the top row, blue and white on the bottom one.
+        let path = std::env::temp_dir().join(format!("image_texture_{}.ppm", std::process::id()));
+        fs::write(
+            &path,
+            "P3\n2 2\n255\n255 0 0  0 255 0\n0 0 255  255 255 255\n",
+        )
+        .unwrap();
+        let texture = ImageTexture::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let red = Color::new(1.0, 0.0, 0.0);
+        let green = Color::new(0.0, 1.0, 0.0);
+        let blue = Color::new(0.0, 0.0, 1.0);
+        let white = Color::new(1.0, 1.0, 1.0);
+        let p = Point3::default();
+
+        assert_eq!(texture.value(0.25, 0.75, &p), red);
+        assert_eq!(texture.value(0.75, 0.75, &p), green);
+        assert_eq!(texture.value(0.25, 0.25, &p), blue);
+        assert_eq!(texture.value(0.75, 0.25, &p), white);
+
+        // Edges and coordinates outside [0, 1] clamp to the border texels.
+        assert_eq!(texture.value(0.0, 1.0, &p), red);
+        assert_eq!(texture.value(1.0, 0.0, &p), white);
+        assert_eq!(texture.value(-3.0, 2.0, &p), red);
+        assert_eq!(texture.value(5.0, -1.0, &p), white);
+    }
+}
@@ -0,0 +1,33 @@
+pub mod checker;
+pub mod image_texture;
+pub mod noise;
+pub mod perlin;
+pub mod solid_color;
+
+use crate::core::Texture;
+use crate::vec3::{Color, Point3};
+
+#[derive(Clone)]
+pub enum TextureType {
+    SolidColor(solid_color::SolidColor),
+    Checker(checker::Checker),
+    Noise(noise::Noise),
+    Image(image_texture::ImageTexture),
+}
+
+impl Texture for TextureType {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        match self {
+            TextureType::SolidColor(s) => s.value(u, v, p),
+            TextureType::Checker(c) => c.value(u, v, p),
+            TextureType::Noise(n) => n.value(u, v, p),
+            TextureType::Image(i) => i.value(u, v, p),
+        }
+    }
+}
+
+impl From<Color> for TextureType {
+    fn from(color: Color) -> Self {
+        TextureType::SolidColor(solid_color::SolidColor::new(color))
+    }
+}
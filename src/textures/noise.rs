@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use crate::core::Texture;
+use crate::textures::perlin::Perlin;
+use crate::vec3::{Color, Point3};
+
+const TURBULENCE_DEPTH: u32 = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoiseKind {
+    /// Plain smooth Perlin noise.
+    Perlin,
+    /// Several octaves of noise summed for a cloudy look.
+    Turbulence,
+    /// Sine bands along z, distorted by turbulence.
+    Marble,
+}
+
+#[derive(Clone)]
+pub struct Noise {
+    perlin: Arc<Perlin>,
+    scale: f64,
+    kind: NoiseKind,
+}
+
+impl Noise {
+    /// Noise whose random lattice is generated from `seed`.
+    pub fn new(kind: NoiseKind, scale: f64, seed: u64) -> Noise {
+        Noise {
+            perlin: Arc::new(Perlin::new(seed)),
+            scale,
+            kind,
+        }
+    }
+}
+
+impl Texture for Noise {
+    fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+        let white = Color::new(1.0, 1.0, 1.0);
+        match self.kind {
+            NoiseKind::Perlin => white * 0.5 * (1.0 + self.perlin.noise(&(self.scale * *p))),
+            NoiseKind::Turbulence => white * self.perlin.turb(&(self.scale * *p), TURBULENCE_DEPTH),
+            NoiseKind::Marble => {
+                let phase = self.scale * p.z() + 10.0 * self.perlin.turb(p, TURBULENCE_DEPTH);
+                white * 0.5 * (1.0 + phase.sin())
+            }
+        }
+    }
+}
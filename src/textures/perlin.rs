@@ -0,0 +1,123 @@
+use crate::core::rng::Rng;
+use crate::vec3::{Point3, Vec3};
+
+const POINT_COUNT: usize = 256;
+
+/// Gradient noise over a lattice of random unit vectors.
+pub struct Perlin {
+    ranvec: Vec<Vec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Perlin {
+        let mut rng = Rng::new(seed);
+        let ranvec = (0..POINT_COUNT)
+            .map(|_| Vec3::random_range_vec3(-1.0, 1.0, &mut rng).unit_vector())
+            .collect();
+        Perlin {
+            ranvec,
+            perm_x: Perlin::generate_perm(&mut rng),
+            perm_y: Perlin::generate_perm(&mut rng),
+            perm_z: Perlin::generate_perm(&mut rng),
+        }
+    }
+
+    /// Smooth noise in roughly [-1, 1].
+    pub fn noise(&self, p: &Point3) -> f64 {
+        let u = p.x() - p.x().floor();
+        let v = p.y() - p.y().floor();
+        let w = p.z() - p.z().floor();
+        let i = p.x().floor() as i64;
+        let j = p.y().floor() as i64;
+        let k = p.z().floor() as i64;
+
+        let mut c = [[[Vec3::default(); 2]; 2]; 2];
+        for (di, plane) in c.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    *corner = self.ranvec[self.perm_x[Perlin::wrap(i + di as i64)]
+                        ^ self.perm_y[Perlin::wrap(j + dj as i64)]
+                        ^ self.perm_z[Perlin::wrap(k + dk as i64)]];
+                }
+            }
+        }
+
+        Perlin::interpolate(&c, u, v, w)
+    }
+
+    /// Sum of `depth` octaves of absolute noise.
+    pub fn turb(&self, p: &Point3, depth: u32) -> f64 {
+        let mut accum = 0.0;
+        let mut temp_p = *p;
+        let mut weight = 1.0;
+        for _ in 0..depth {
+            accum += weight * self.noise(&temp_p);
+            weight *= 0.5;
+            temp_p *= 2.0;
+        }
+        accum.abs()
+    }
+
+    fn wrap(i: i64) -> usize {
+        (i & (POINT_COUNT as i64 - 1)) as usize
+    }
+
+    fn generate_perm(rng: &mut Rng) -> Vec<usize> {
+        let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+        for i in (1..POINT_COUNT).rev() {
+            p.swap(i, rng.below(i + 1));
+        }
+        p
+    }
+
+    fn interpolate(c: &[[[Vec3; 2]; 2]; 2], u: f64, v: f64, w: f64) -> f64 {
+        let uu = u * u * (3.0 - 2.0 * u);
+        let vv = v * v * (3.0 - 2.0 * v);
+        let ww = w * w * (3.0 - 2.0 * w);
+        let mut accum = 0.0;
+
+        for (i, plane) in c.iter().enumerate() {
+            for (j, row) in plane.iter().enumerate() {
+                for (k, corner) in row.iter().enumerate() {
+                    let (fi, fj, fk) = (i as f64, j as f64, k as f64);
+                    let weight = Vec3::new(u - fi, v - fj, w - fk);
+                    accum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                        * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                        * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                        * Vec3::dot(*corner, weight);
+                }
+            }
+        }
+        accum
+    }
+}
+
+impl Default for Perlin {
+    fn default() -> Self {
+        Perlin::new(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seed_determinism_test() {
+        let (a, b, other) = (Perlin::new(7), Perlin::new(7), Perlin::new(8));
+        let points = [
+            Point3::new(0.3, 1.7, -2.2),
+            Point3::new(10.1, -4.6, 0.9),
+            Point3::new(-0.5, 0.25, 3.75),
+        ];
+
+        for p in points.iter() {
+            assert_eq!(a.noise(p), b.noise(p));
+            assert_eq!(a.turb(p, 7), b.turb(p, 7));
+        }
+        assert!(points.iter().any(|p| a.noise(p) != other.noise(p)));
+    }
+}
@@ -0,0 +1,197 @@
+use crate::core::clamp;
+use crate::vec3::Color;
+
+/// Maps linear radiance to the displayable 0 to 1 range.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ToneMapper {
+    /// Cuts off everything above 1.
+    Clamp,
+    /// `L / (1 + L)` on luminance; never reaches white.
+    Reinhard,
+    /// Reinhard with luminance `white_point` mapped to white.
+    ReinhardExtended,
+    /// Narkowicz's fit of the ACES filmic curve.
+    Aces,
+    /// John Hable's Uncharted 2 filmic curve.
+    Hable,
+}
+
+impl ToneMapper {
+    pub const NAMES: &'static [&'static str] =
+        &["clamp", "reinhard", "reinhard_extended", "aces", "hable"];
+
+    pub fn from_name(name: &str) -> Option<ToneMapper> {
+        match name {
+            "clamp" => Some(ToneMapper::Clamp),
+            "reinhard" => Some(ToneMapper::Reinhard),
+            "reinhard_extended" => Some(ToneMapper::ReinhardExtended),
+            "aces" => Some(ToneMapper::Aces),
+            "hable" => Some(ToneMapper::Hable),
+            _ => None,
+        }
+    }
+}
+
+/// Encodes display-referred values for an 8-bit image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transfer {
+    /// The piecewise sRGB curve.
+    Srgb,
+    /// A plain power curve with exponent `1 / gamma`.
+    Gamma,
+}
+
+impl Transfer {
+    pub const NAMES: &'static [&'static str] = &["srgb", "gamma"];
+
+    pub fn from_name(name: &str) -> Option<Transfer> {
+        match name {
+            "srgb" => Some(Transfer::Srgb),
+            "gamma" => Some(Transfer::Gamma),
+            _ => None,
+        }
+    }
+}
+
+/// Conversion from the linear float framebuffer to 8-bit output. The
+/// default, clamping with gamma 2, matches the renderer's original output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DisplayTransform {
+    /// Exposure adjustment in stops; each stop doubles the radiance.
+    pub exposure: f64,
+    pub tone_mapper: ToneMapper,
+    /// Luminance mapped to white by `ToneMapper::ReinhardExtended`.
+    pub white_point: f64,
+    pub transfer: Transfer,
+    /// Exponent used by `Transfer::Gamma`.
+    pub gamma: f64,
+}
+
+impl Default for DisplayTransform {
+    fn default() -> Self {
+        DisplayTransform {
+            exposure: 0.0,
+            tone_mapper: ToneMapper::Clamp,
+            white_point: 4.0,
+            transfer: Transfer::Gamma,
+            gamma: 2.0,
+        }
+    }
+}
+
+impl DisplayTransform {
+    /// Maps linear radiance to encoded display values between 0 and 1.
+    pub fn apply(&self, color: Color) -> Color {
+        let c = color * 2f64.powf(self.exposure);
+        let c = Color::new(c.x().max(0.0), c.y().max(0.0), c.z().max(0.0));
+        let mapped = match self.tone_mapper {
+            ToneMapper::Clamp => c,
+            ToneMapper::Reinhard => scale_luminance(c, |l| l / (1.0 + l)),
+            ToneMapper::ReinhardExtended => {
+                let white_squared = self.white_point * self.white_point;
+                scale_luminance(c, |l| l * (1.0 + l / white_squared) / (1.0 + l))
+            }
+            ToneMapper::Aces => per_channel(c, |x| {
+                (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+            }),
+            ToneMapper::Hable => {
+                const EXPOSURE_BIAS: f64 = 2.0;
+                const WHITE: f64 = 11.2;
+                let white_scale = 1.0 / hable(WHITE);
+                per_channel(c, |x| hable(EXPOSURE_BIAS * x) * white_scale)
+            }
+        };
+
+        per_channel(mapped, |x| {
+            let x = clamp(x, 0.0, 1.0);
+            match self.transfer {
+                Transfer::Srgb if x <= 0.0031308 => 12.92 * x,
+                Transfer::Srgb => 1.055 * x.powf(1.0 / 2.4) - 0.055,
+                Transfer::Gamma => x.powf(1.0 / self.gamma),
+            }
+        })
+    }
+
+    /// Tone maps and encodes `color` as 8-bit RGB.
+    pub fn quantize(&self, color: Color) -> [u8; 3] {
+        let c = self.apply(color);
+        [c.x(), c.y(), c.z()].map(|v| (256.0 * clamp(v, 0.0, 0.999)) as u8)
+    }
+}
+
+/// Rec. 709 luminance of a linear colour.
+pub fn luminance(c: Color) -> f64 {
+    0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()
+}
+
+/// Tone maps luminance only, keeping the hue of the colour.
+fn scale_luminance<F: Fn(f64) -> f64>(c: Color, curve: F) -> Color {
+    let l = luminance(c);
+    if l <= 0.0 {
+        return c;
+    }
+    c * (curve(l) / l)
+}
+
+fn per_channel<F: Fn(f64) -> f64>(c: Color, f: F) -> Color {
+    Color::new(f(c.x()), f(c.y()), f(c.z()))
+}
+
+fn hable(x: f64) -> f64 {
+    const A: f64 = 0.15;
+    const B: f64 = 0.50;
+    const C: f64 = 0.10;
+    const D: f64 = 0.20;
+    const E: f64 = 0.02;
+    const F: f64 = 0.30;
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grey(v: f64) -> Color {
+        Color::new(v, v, v)
+    }
+
+    #[test]
+    fn default_matches_square_root_gamma_test() {
+        let display = DisplayTransform::default();
+
+        assert_eq!(display.quantize(grey(0.25)), [128, 128, 128]);
+        assert_eq!(display.quantize(grey(4.0)), [255, 255, 255]);
+        assert_eq!(display.quantize(grey(-1.0)), [0, 0, 0]);
+    }
+
+    #[test]
+    fn tone_mapper_test() {
+        let mut display = DisplayTransform {
+            tone_mapper: ToneMapper::ReinhardExtended,
+            gamma: 1.0,
+            ..DisplayTransform::default()
+        };
+        assert!((display.apply(grey(4.0)).x() - 1.0).abs() < 1e-12);
+
+        display.tone_mapper = ToneMapper::Reinhard;
+        assert!((display.apply(grey(1.0)).x() - 0.5).abs() < 1e-12);
+
+        display.exposure = 1.0;
+        assert!((display.apply(grey(0.5)).x() - 0.5).abs() < 1e-12);
+
+        display.tone_mapper = ToneMapper::Hable;
+        assert!(display.apply(grey(100.0)).x() > 0.99);
+    }
+
+    #[test]
+    fn srgb_transfer_test() {
+        let display = DisplayTransform {
+            transfer: Transfer::Srgb,
+            ..DisplayTransform::default()
+        };
+
+        assert_eq!(display.apply(grey(0.0)).x(), 0.0);
+        assert!((display.apply(grey(1.0)).x() - 1.0).abs() < 1e-12);
+        assert!((display.apply(grey(0.18)).x() - 0.4613).abs() < 1e-4);
+    }
+}
@@ -1,5 +1,5 @@
-use crate::core;
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub};
+use crate::core::rng::Rng;
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub};
 
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Vec3 {
@@ -109,43 +109,51 @@ impl Neg for Vec3 {
     }
 }
 
+impl Index<usize> for Vec3 {
+    type Output = f64;
+    fn index(&self, axis: usize) -> &f64 {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vec3 index out of range: {}", axis),
+        }
+    }
+}
+
 impl Vec3 {
     pub fn new(x: f64, y: f64, z: f64) -> Vec3 {
         Vec3 { x, y, z }
     }
 
-    pub fn random_vec3() -> Vec3 {
-        Vec3::new(core::random_f64(), core::random_f64(), core::random_f64())
+    pub fn random_vec3(rng: &mut Rng) -> Vec3 {
+        Vec3::new(rng.f64(), rng.f64(), rng.f64())
     }
 
-    pub fn random_range_vec3(min: f64, max: f64) -> Vec3 {
+    pub fn random_range_vec3(min: f64, max: f64, rng: &mut Rng) -> Vec3 {
         Vec3::new(
-            core::random_range_f64(min, max),
-            core::random_range_f64(min, max),
-            core::random_range_f64(min, max),
+            rng.range_f64(min, max),
+            rng.range_f64(min, max),
+            rng.range_f64(min, max),
         )
     }
 
-    pub fn random_in_unit_sphere() -> Vec3 {
+    pub fn random_in_unit_sphere(rng: &mut Rng) -> Vec3 {
         loop {
-            let p = Vec3::random_range_vec3(-1.0, 1.0);
+            let p = Vec3::random_range_vec3(-1.0, 1.0, rng);
             if p.length_squared() < 1.0 {
                 return p;
             }
         }
     }
 
-    pub fn random_unit_vector() -> Vec3 {
-        Vec3::random_in_unit_sphere().unit_vector()
+    pub fn random_unit_vector(rng: &mut Rng) -> Vec3 {
+        Vec3::random_in_unit_sphere(rng).unit_vector()
     }
 
-    pub fn random_in_unit_disk() -> Vec3 {
+    pub fn random_in_unit_disk(rng: &mut Rng) -> Vec3 {
         loop {
-            let p = Vec3::new(
-                core::random_range_f64(-1.0, 1.0),
-                core::random_range_f64(-1.0, 1.0),
-                0.0,
-            );
+            let p = Vec3::new(rng.range_f64(-1.0, 1.0), rng.range_f64(-1.0, 1.0), 0.0);
             if p.length_squared() < 1.0 {
                 return p;
             }
@@ -192,13 +200,29 @@ impl Vec3 {
         }
     }
 
+    pub fn minimum(v1: Vec3, v2: Vec3) -> Vec3 {
+        Vec3 {
+            x: v1.x.min(v2.x),
+            y: v1.y.min(v2.y),
+            z: v1.z.min(v2.z),
+        }
+    }
+
+    pub fn maximum(v1: Vec3, v2: Vec3) -> Vec3 {
+        Vec3 {
+            x: v1.x.max(v2.x),
+            y: v1.y.max(v2.y),
+            z: v1.z.max(v2.z),
+        }
+    }
+
     pub fn unit_vector(&self) -> Vec3 {
         *self / self.length()
     }
 
     pub fn near_zero(&self) -> bool {
         let s: f64 = 1e-8;
-        self.x.abs() < s && self.y.abs() < s && self.z < s
+        self.x.abs() < s && self.y.abs() < s && self.z.abs() < s
     }
 
     pub fn x(&self) -> f64 {
@@ -220,9 +244,9 @@ mod tests {
 
     #[test]
     fn random_unit_vector_test() {
-        let v1 = Vec3::random_unit_vector();
+        let v1 = Vec3::random_unit_vector(&mut Rng::new(1));
 
-        assert!(v1.length_squared() < 1.0);
+        assert!((v1.length_squared() - 1.0).abs() < 1e-12);
     }
 
     #[test]
@@ -331,6 +355,22 @@ mod tests {
         assert_eq!(Vec3::cross(v1, v2), Vec3::new(-3.0, 6.0, -3.0));
     }
 
+    #[test]
+    fn index_test() {
+        let v1 = Vec3::new(1.0, 2.0, 3.0);
+
+        assert_eq!((v1[0], v1[1], v1[2]), (1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn minimum_maximum_test() {
+        let v1 = Vec3::new(1.0, 5.0, -3.0);
+        let v2 = Vec3::new(4.0, 2.0, -6.0);
+
+        assert_eq!(Vec3::minimum(v1, v2), Vec3::new(1.0, 2.0, -6.0));
+        assert_eq!(Vec3::maximum(v1, v2), Vec3::new(4.0, 5.0, -3.0));
+    }
+
     #[test]
     fn unit_vector_test() {
         let x = f64::sqrt(1.2);
@@ -341,4 +381,12 @@ mod tests {
         let l = v1.length();
         assert_eq!(v1.unit_vector(), v1 / l);
     }
+
+    #[test]
+    fn near_zero_test() {
+        assert!(Vec3::new(0.0, 0.0, 0.0).near_zero());
+        assert!(Vec3::new(1e-9, -1e-9, 1e-9).near_zero());
+        assert!(!Vec3::new(0.0, 0.0, -1.0).near_zero());
+        assert!(!Vec3::new(-1.0, 0.0, 0.0).near_zero());
+    }
 }